@@ -1,61 +1,670 @@
+use crate::error;
 use crate::expr::{
-    Expr, Visitor, Binary, Grouping, Literal, Unary
+    self, Assign, Binary, Call, Conditional, Expr, Get, Grouping, Index, List, Literal, Logical, Map, Match, Set, SetIndex, This,
+    Unary, Variable,
 };
-use crate::tokentype::Literals;
-pub struct AstPrinter;
+use crate::parser::Parser;
+use crate::pattern::Pattern;
+use crate::scanner::Scanner;
+use crate::stmt::{self, Block, Class, Continue, Enum, Expression, For, ForIn, Function, If, Import, Print, Return, Stmt, Throw, Trait, Try, Var, While, Yield};
+use crate::token::{Comment, Token};
+use crate::tokentype::{FunctionKind, Literals, TokenType};
 
-impl AstPrinter {
-    pub fn print(&self, expr: &Expr) -> String {
-        expr.accept(self)
+/// Formats a Lox program into canonical source. Syntax errors are reported
+/// as usual and leave nothing to format.
+pub fn format(source: &String) -> Result<String, ()> {
+    error::reset();
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens().clone();
+    let statements = Parser::new(&tokens).parse().map_err(|_| ())?;
+    if error::had_error() {
+        return Err(());
     }
+    Ok(AstPrinter::new(&tokens, scanner.comments()).print(&statements))
 }
 
-impl Visitor<String> for AstPrinter {
-    fn visit_binary_expr(&self, expr: &Binary) -> String {
-        format!(
-            "({} {} {})",
-            expr.operator.lexeme,
-            expr.left.accept(self),
-            expr.right.accept(self)
-        )
-    }
-    fn visit_grouping_expr(&self, expr: &Grouping) -> String {
-        format!("(group {})", expr.expression.accept(self))
-    }
-    fn visit_literal_expr(&self, expr: &Literal) -> String {
-        match expr.value {
-            Literals::NUMBER(n) => format!("{}", n),
-            Literals::STRING(ref s) => format!("{}", s),
-            Literals::BOOL(s) => format!("{}", s),
-            Literals::NIL(s) => format!("{:?}", s),
-        }
-    }
-    fn visit_unary_expr(&self, expr: &Unary) -> String {
-        format!(
-            "({} {})",
-            expr.operator.lexeme,
-            expr.right.accept(self)
-        )
+#[derive(Clone, Copy, PartialEq)]
+enum Break {
+    /// Start of a statement, at the current block's indentation.
+    STATEMENT,
+    /// A line comment ended the line in the middle of a statement.
+    CONTINUATION,
+    /// A line comment ended the line before a closing delimiter or `else`,
+    /// `catch` or `finally`, which go back to the enclosing indentation.
+    RESUME,
+}
+
+/// Re-emits source from the AST. Every token written is matched against the
+/// scanned token stream so that comments can be put back next to the tokens
+/// they preceded.
+pub struct AstPrinter<'a> {
+    tokens: &'a [Token],
+    comments: &'a [Comment],
+    cursor: usize,
+    next_comment: usize,
+    out: String,
+    depth: usize,
+    /// Source line of the last token or comment written.
+    last_line: u64,
+    pending: Option<Break>,
+    block_start: bool,
+}
+
+impl<'a> AstPrinter<'a> {
+    pub fn new(tokens: &'a [Token], comments: &'a [Comment]) -> AstPrinter<'a> {
+        AstPrinter {
+            tokens,
+            comments,
+            cursor: 0,
+            next_comment: 0,
+            out: String::new(),
+            depth: 0,
+            last_line: 0,
+            pending: None,
+            block_start: false,
+        }
+    }
+
+    pub fn print(mut self, statements: &[Stmt]) -> String {
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.pending = Some(Break::STATEMENT);
+        self.flush_comments(self.tokens.len());
+        self.trim_end();
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        self.pending = Some(Break::STATEMENT);
+        stmt.accept(self);
+    }
+
+    /// Bodies of `if`, `while` and `for` stay on the header's line.
+    fn body(&mut self, stmt: &Stmt) {
+        self.space();
+        stmt.accept(self);
+    }
+
+    fn block(&mut self, statements: &[Stmt]) {
+        self.token(TokenType::LEFT_BRACE, "{");
+        self.depth += 1;
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.close_brace(!statements.is_empty());
+    }
+
+    /// Closes a block on its own line unless it is empty, so `{}` stays on
+    /// one line. Comments before the brace stay inside the block.
+    fn close_brace(&mut self, has_body: bool) {
+        let brace = self.tokens[self.cursor..]
+            .iter()
+            .position(|token| token.token_type == TokenType::RIGHT_BRACE)
+            .map(|offset| self.cursor + offset);
+        let has_comment = brace.is_some_and(|brace| self.comment_before(brace));
+        if has_body || has_comment {
+            self.pending = Some(Break::STATEMENT);
+            self.flush_comments(brace.unwrap_or(self.tokens.len()));
+            self.pending = Some(Break::STATEMENT);
+        }
+        self.depth -= 1;
+        self.token(TokenType::RIGHT_BRACE, "}");
+    }
+
+    /// The body of a class or trait, one member per line.
+    fn methods(&mut self, methods: &[Function]) {
+        self.token(TokenType::LEFT_BRACE, "{");
+        self.depth += 1;
+        for method in methods.iter() {
+            self.pending = Some(Break::STATEMENT);
+            self.function(method);
+        }
+        self.close_brace(!methods.is_empty());
+    }
+
+    fn function(&mut self, function: &Function) {
+        match function.kind {
+            FunctionKind::STATIC => {
+                self.token(TokenType::CLASS, "class");
+                self.space();
+            }
+            FunctionKind::SETTER => {
+                self.token(TokenType::IDENTIFIER, "set");
+                self.space();
+            }
+            _ => {}
+        }
+        self.token(TokenType::IDENTIFIER, &function.name.lexeme);
+        if function.kind != FunctionKind::GETTER {
+            self.token(TokenType::LEFT_PAREN, "(");
+            for (i, (param, default)) in function.params.iter().zip(function.defaults.iter()).enumerate() {
+                if i > 0 {
+                    self.token(TokenType::COMMA, ",");
+                    self.space();
+                }
+                self.token(TokenType::IDENTIFIER, &param.lexeme);
+                if let Some(default) = default {
+                    self.space();
+                    self.token(TokenType::EQUAL, "=");
+                    self.space();
+                    default.accept(self);
+                }
+            }
+            if let Some(rest) = &function.rest {
+                if !function.params.is_empty() {
+                    self.token(TokenType::COMMA, ",");
+                    self.space();
+                }
+                self.token(TokenType::DOT_DOT_DOT, "...");
+                self.token(TokenType::IDENTIFIER, &rest.lexeme);
+            }
+            self.token(TokenType::RIGHT_PAREN, ")");
+        }
+        self.space();
+        self.block(&function.body);
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::WILDCARD(_) => self.token(TokenType::IDENTIFIER, "_"),
+            Pattern::LITERAL(literal) => literal.accept(self),
+            Pattern::BINDING(name) => self.token(TokenType::IDENTIFIER, &name.lexeme),
+            Pattern::ALTERNATIVE(alternatives) => {
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        self.space();
+                        self.token(TokenType::PIPE, "|");
+                        self.space();
+                    }
+                    self.pattern(alternative);
+                }
+            }
+            Pattern::INSTANCE(class, fields) => {
+                class.accept(self);
+                self.token(TokenType::LEFT_BRACE, "{");
+                for (i, (name, field)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.token(TokenType::COMMA, ",");
+                        self.space();
+                    }
+                    self.token(TokenType::IDENTIFIER, &name.lexeme);
+                    // `x` is shorthand for `x: x`.
+                    if !matches!(field, Pattern::BINDING(binding) if binding.lexeme == name.lexeme) {
+                        self.token(TokenType::COLON, ":");
+                        self.space();
+                        self.pattern(field);
+                    }
+                }
+                self.token(TokenType::RIGHT_BRACE, "}");
+            }
+        }
+    }
+
+    fn operator(&mut self, left: &Expr, operator: &Token, right: &Expr) {
+        left.accept(self);
+        self.space();
+        self.token(operator.token_type, &operator.lexeme);
+        self.space();
+        right.accept(self);
+    }
+
+    fn space(&mut self) {
+        self.out.push(' ');
+    }
+
+    /// Writes the next token, preceded by any comments that came before it
+    /// in the source.
+    fn token(&mut self, token_type: TokenType, text: &str) {
+        let mut index = self.cursor;
+        while index < self.tokens.len() && self.tokens[index].token_type != token_type {
+            index += 1;
+        }
+        let (line, text) = match self.tokens.get(index) {
+            Some(token) => {
+                self.cursor = index + 1;
+                let resumes = matches!(
+                    token_type,
+                    TokenType::RIGHT_BRACE
+                        | TokenType::RIGHT_BRACKET
+                        | TokenType::RIGHT_PAREN
+                        | TokenType::ELSE
+                        | TokenType::CATCH
+                        | TokenType::FINALLY
+                );
+                if resumes && self.pending.is_none() && self.comment_before(index) {
+                    self.pending = Some(Break::RESUME);
+                }
+                self.flush_comments(index);
+                match token_type {
+                    // Keep numbers as written rather than as they print.
+                    TokenType::NUMBER => (token.line, token.lexeme.as_str()),
+                    _ => (token.line, text),
+                }
+            }
+            None => (self.last_line, text),
+        };
+        if let Some(brk) = self.pending.take() {
+            let blank = brk == Break::STATEMENT && token_type != TokenType::RIGHT_BRACE;
+            self.new_line(line, brk, blank);
+        }
+        self.out.push_str(text);
+        self.last_line = line;
+        self.block_start = token_type == TokenType::LEFT_BRACE;
+    }
+
+    fn comment_before(&self, index: usize) -> bool {
+        self.comments.get(self.next_comment).is_some_and(|comment| comment.token <= index)
+    }
+
+    fn flush_comments(&mut self, index: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.token > index {
+                break;
+            }
+            self.next_comment += 1;
+            if comment.line == self.last_line {
+                self.trim_end();
+                self.space();
+            } else {
+                let brk = self.pending.unwrap_or(Break::CONTINUATION);
+                self.new_line(comment.line, brk, true);
+            }
+            self.out.push_str(&comment.text);
+            self.last_line = comment.line;
+            self.block_start = false;
+            self.pending.get_or_insert(Break::CONTINUATION);
+        }
+    }
+
+    /// Starts a new output line, keeping a single blank line where the source
+    /// had one or more.
+    fn new_line(&mut self, line: u64, brk: Break, blank: bool) {
+        self.trim_end();
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            if blank && !self.block_start && line > self.last_line + 1 {
+                self.out.push('\n');
+            }
+        }
+        let indent = match brk {
+            Break::STATEMENT | Break::RESUME => self.depth,
+            Break::CONTINUATION => self.depth + 1,
+        };
+        self.out.push_str(&"  ".repeat(indent));
+    }
+
+    fn trim_end(&mut self) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn test_print_ast() {
-        use crate::tokentype::TokenType;
-        use crate::token::Token;
-        let minus = Token::new(TokenType::MINUS, '-'.to_string(), None, 1);
-        let star = Token::new(TokenType::STAR, "*".to_string(), None, 1);
-        let num1 = Literals::NUMBER(123.0);
-        let num2 = Literals::NUMBER(45.67);
-        let expression = Binary::new(
-            Unary::new(minus, Literal::new(num1)),
-            star,
-            Grouping::new(Literal::new(num2)),
-        );
-        let printer = AstPrinter {};
-        println!("{}", printer.print(&expression));
+impl<'a> expr::Visitor<()> for AstPrinter<'a> {
+    fn visit_binary_expr(&mut self, expr: &Binary) {
+        self.operator(&expr.left, &expr.operator, &expr.right);
+    }
+    fn visit_grouping_expr(&mut self, expr: &Grouping) {
+        self.token(TokenType::LEFT_PAREN, "(");
+        expr.expression.accept(self);
+        self.token(TokenType::RIGHT_PAREN, ")");
+    }
+    fn visit_literal_expr(&mut self, expr: &Literal) {
+        match &expr.value {
+            Literals::NUMBER(n) => self.token(TokenType::NUMBER, &n.to_string()),
+            Literals::INTEGER(n) => self.token(TokenType::NUMBER, &n.to_string()),
+            Literals::STRING(s) => self.token(TokenType::STRING, &format!("\"{}\"", s)),
+            Literals::BOOL(true) => self.token(TokenType::TRUE, "true"),
+            Literals::BOOL(false) => self.token(TokenType::FALSE, "false"),
+            Literals::NIL(_) => self.token(TokenType::NIL, "nil"),
+        }
+    }
+    fn visit_unary_expr(&mut self, expr: &Unary) {
+        self.token(expr.operator.token_type, &expr.operator.lexeme);
+        expr.right.accept(self);
+    }
+    fn visit_variable_expr(&mut self, expr: &Variable) {
+        self.token(TokenType::IDENTIFIER, &expr.name.lexeme);
+    }
+    fn visit_assign_expr(&mut self, expr: &Assign) {
+        self.token(TokenType::IDENTIFIER, &expr.name.lexeme);
+        self.space();
+        self.token(TokenType::EQUAL, "=");
+        self.space();
+        expr.value.accept(self);
+    }
+    fn visit_logical_expr(&mut self, expr: &Logical) {
+        self.operator(&expr.left, &expr.operator, &expr.right);
+    }
+    fn visit_call_expr(&mut self, expr: &Call) {
+        expr.callee.accept(self);
+        self.token(TokenType::LEFT_PAREN, "(");
+        let first_named = expr.arguments.len() - expr.names.len();
+        for (i, argument) in expr.arguments.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::COMMA, ",");
+                self.space();
+            }
+            if i >= first_named {
+                self.token(TokenType::IDENTIFIER, &expr.names[i - first_named].lexeme);
+                self.token(TokenType::COLON, ":");
+                self.space();
+            }
+            argument.accept(self);
+        }
+        self.token(TokenType::RIGHT_PAREN, ")");
+    }
+    fn visit_get_expr(&mut self, expr: &Get) {
+        expr.object.accept(self);
+        if expr.optional {
+            self.token(TokenType::QUESTION_DOT, "?.");
+        } else {
+            self.token(TokenType::DOT, ".");
+        }
+        self.token(TokenType::IDENTIFIER, &expr.name.lexeme);
+    }
+    fn visit_set_expr(&mut self, expr: &Set) {
+        expr.object.accept(self);
+        self.token(TokenType::DOT, ".");
+        self.token(TokenType::IDENTIFIER, &expr.name.lexeme);
+        self.space();
+        self.token(TokenType::EQUAL, "=");
+        self.space();
+        expr.value.accept(self);
+    }
+    fn visit_this_expr(&mut self, _expr: &This) {
+        self.token(TokenType::THIS, "this");
+    }
+    fn visit_list_expr(&mut self, expr: &List) {
+        self.token(TokenType::LEFT_BRACKET, "[");
+        for (i, element) in expr.elements.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::COMMA, ",");
+                self.space();
+            }
+            element.accept(self);
+        }
+        self.token(TokenType::RIGHT_BRACKET, "]");
+    }
+    fn visit_map_expr(&mut self, expr: &Map) {
+        self.token(TokenType::LEFT_BRACE, "{");
+        for (i, (key, value)) in expr.keys.iter().zip(expr.values.iter()).enumerate() {
+            if i > 0 {
+                self.token(TokenType::COMMA, ",");
+                self.space();
+            }
+            key.accept(self);
+            self.token(TokenType::COLON, ":");
+            self.space();
+            value.accept(self);
+        }
+        self.token(TokenType::RIGHT_BRACE, "}");
+    }
+    fn visit_index_expr(&mut self, expr: &Index) {
+        expr.object.accept(self);
+        self.token(TokenType::LEFT_BRACKET, "[");
+        expr.index.accept(self);
+        self.token(TokenType::RIGHT_BRACKET, "]");
+    }
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) {
+        expr.object.accept(self);
+        self.token(TokenType::LEFT_BRACKET, "[");
+        expr.index.accept(self);
+        self.token(TokenType::RIGHT_BRACKET, "]");
+        self.space();
+        self.token(TokenType::EQUAL, "=");
+        self.space();
+        expr.value.accept(self);
+    }
+    fn visit_conditional_expr(&mut self, expr: &Conditional) {
+        expr.condition.accept(self);
+        self.space();
+        self.token(TokenType::QUESTION, "?");
+        self.space();
+        expr.then_branch.accept(self);
+        self.space();
+        self.token(TokenType::COLON, ":");
+        self.space();
+        expr.else_branch.accept(self);
+    }
+    /// One arm per line, with commas only between arms.
+    fn visit_match_expr(&mut self, expr: &Match) {
+        self.token(TokenType::MATCH, "match");
+        self.space();
+        self.token(TokenType::LEFT_PAREN, "(");
+        expr.value.accept(self);
+        self.token(TokenType::RIGHT_PAREN, ")");
+        self.space();
+        self.token(TokenType::LEFT_BRACE, "{");
+        self.depth += 1;
+        for (i, arm) in expr.arms.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::COMMA, ",");
+            }
+            self.pending = Some(Break::STATEMENT);
+            self.pattern(&arm.pattern);
+            self.space();
+            self.token(TokenType::ARROW, "=>");
+            self.space();
+            arm.body.accept(self);
+        }
+        self.close_brace(!expr.arms.is_empty());
+    }
+}
+
+impl<'a> stmt::Visitor<()> for AstPrinter<'a> {
+    fn visit_expression_stmt(&mut self, stmt: &Expression) {
+        stmt.expression.accept(self);
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_print_stmt(&mut self, stmt: &Print) {
+        self.token(TokenType::PRINT, "print");
+        self.space();
+        stmt.expression.accept(self);
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_var_stmt(&mut self, stmt: &Var) {
+        if stmt.constant {
+            self.token(TokenType::CONST, "const");
+        } else {
+            self.token(TokenType::VAR, "var");
+        }
+        self.space();
+        self.token(TokenType::IDENTIFIER, &stmt.name.lexeme);
+        if let Some(initializer) = &stmt.initializer {
+            self.space();
+            self.token(TokenType::EQUAL, "=");
+            self.space();
+            initializer.accept(self);
+        }
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_block_stmt(&mut self, stmt: &Block) {
+        self.block(&stmt.statements);
+    }
+    fn visit_if_stmt(&mut self, stmt: &If) {
+        self.token(TokenType::IF, "if");
+        self.space();
+        self.token(TokenType::LEFT_PAREN, "(");
+        stmt.condition.accept(self);
+        self.token(TokenType::RIGHT_PAREN, ")");
+        self.body(&stmt.then_branch);
+        if let Some(else_branch) = &stmt.else_branch {
+            self.space();
+            self.token(TokenType::ELSE, "else");
+            self.body(else_branch);
+        }
+    }
+    fn visit_while_stmt(&mut self, stmt: &While) {
+        self.token(TokenType::WHILE, "while");
+        self.space();
+        self.token(TokenType::LEFT_PAREN, "(");
+        stmt.condition.accept(self);
+        self.token(TokenType::RIGHT_PAREN, ")");
+        self.body(&stmt.body);
+    }
+    fn visit_for_stmt(&mut self, stmt: &For) {
+        self.token(TokenType::FOR, "for");
+        self.space();
+        self.token(TokenType::LEFT_PAREN, "(");
+        match &stmt.initializer {
+            Some(initializer) => initializer.accept(self),
+            None => self.token(TokenType::SEMICOLON, ";"),
+        }
+        if let Some(condition) = &stmt.condition {
+            self.space();
+            condition.accept(self);
+        }
+        self.token(TokenType::SEMICOLON, ";");
+        if let Some(increment) = &stmt.increment {
+            self.space();
+            increment.accept(self);
+        }
+        self.token(TokenType::RIGHT_PAREN, ")");
+        self.body(&stmt.body);
+    }
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) {
+        self.token(TokenType::FOR, "for");
+        self.space();
+        self.token(TokenType::LEFT_PAREN, "(");
+        self.token(TokenType::VAR, "var");
+        self.space();
+        self.token(TokenType::IDENTIFIER, &stmt.name.lexeme);
+        self.space();
+        self.token(TokenType::IN, "in");
+        self.space();
+        stmt.iterable.accept(self);
+        self.token(TokenType::RIGHT_PAREN, ")");
+        self.body(&stmt.body);
+    }
+    fn visit_break_stmt(&mut self, _stmt: &stmt::Break) {
+        self.token(TokenType::BREAK, "break");
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_continue_stmt(&mut self, _stmt: &Continue) {
+        self.token(TokenType::CONTINUE, "continue");
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_function_stmt(&mut self, stmt: &Function) {
+        self.token(TokenType::FUN, "fun");
+        self.space();
+        self.function(stmt);
+    }
+    fn visit_return_stmt(&mut self, stmt: &Return) {
+        self.token(TokenType::RETURN, "return");
+        if let Some(value) = &stmt.value {
+            self.space();
+            value.accept(self);
+        }
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_yield_stmt(&mut self, stmt: &Yield) {
+        self.token(TokenType::YIELD, "yield");
+        self.space();
+        stmt.value.accept(self);
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_import_stmt(&mut self, stmt: &Import) {
+        match &stmt.name {
+            Some(name) => {
+                self.token(TokenType::IMPORT, "import");
+                self.space();
+                self.token(TokenType::STRING, &stmt.path.lexeme);
+                self.space();
+                self.token(TokenType::AS, "as");
+                self.space();
+                self.token(TokenType::IDENTIFIER, &name.lexeme);
+            }
+            None => {
+                self.token(TokenType::FROM, "from");
+                self.space();
+                self.token(TokenType::STRING, &stmt.path.lexeme);
+                self.space();
+                self.token(TokenType::IMPORT, "import");
+                for (i, name) in stmt.names.iter().enumerate() {
+                    if i > 0 {
+                        self.token(TokenType::COMMA, ",");
+                    }
+                    self.space();
+                    self.token(TokenType::IDENTIFIER, &name.lexeme);
+                }
+            }
+        }
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_throw_stmt(&mut self, stmt: &Throw) {
+        self.token(TokenType::THROW, "throw");
+        self.space();
+        stmt.value.accept(self);
+        self.token(TokenType::SEMICOLON, ";");
+    }
+    fn visit_try_stmt(&mut self, stmt: &Try) {
+        self.token(TokenType::TRY, "try");
+        self.space();
+        self.block(&stmt.body);
+        if let Some(name) = &stmt.name {
+            self.space();
+            self.token(TokenType::CATCH, "catch");
+            self.space();
+            self.token(TokenType::LEFT_PAREN, "(");
+            self.token(TokenType::IDENTIFIER, &name.lexeme);
+            self.token(TokenType::RIGHT_PAREN, ")");
+            self.space();
+            self.block(&stmt.catch_body);
+        }
+        if let Some(finally_body) = &stmt.finally_body {
+            self.space();
+            self.token(TokenType::FINALLY, "finally");
+            self.space();
+            self.block(finally_body);
+        }
+    }
+    fn visit_class_stmt(&mut self, stmt: &Class) {
+        self.token(TokenType::CLASS, "class");
+        self.space();
+        self.token(TokenType::IDENTIFIER, &stmt.name.lexeme);
+        self.space();
+        if !stmt.traits.is_empty() {
+            self.token(TokenType::WITH, "with");
+            self.space();
+            for (i, t) in stmt.traits.iter().enumerate() {
+                if i > 0 {
+                    self.token(TokenType::COMMA, ",");
+                    self.space();
+                }
+                t.accept(self);
+            }
+            self.space();
+        }
+        self.methods(&stmt.methods);
+    }
+    fn visit_trait_stmt(&mut self, stmt: &Trait) {
+        self.token(TokenType::TRAIT, "trait");
+        self.space();
+        self.token(TokenType::IDENTIFIER, &stmt.name.lexeme);
+        self.space();
+        self.methods(&stmt.methods);
+    }
+    /// Variants stay on the declaration's line.
+    fn visit_enum_stmt(&mut self, stmt: &Enum) {
+        self.token(TokenType::ENUM, "enum");
+        self.space();
+        self.token(TokenType::IDENTIFIER, &stmt.name.lexeme);
+        self.space();
+        self.token(TokenType::LEFT_BRACE, "{");
+        for (i, variant) in stmt.variants.iter().enumerate() {
+            if i > 0 {
+                self.token(TokenType::COMMA, ",");
+            }
+            self.space();
+            self.token(TokenType::IDENTIFIER, &variant.lexeme);
+        }
+        if !stmt.variants.is_empty() {
+            self.space();
+        }
+        self.token(TokenType::RIGHT_BRACE, "}");
     }
 }
@@ -1,38 +1,287 @@
 use std::env;
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
 
+/// Generates `expr.rs` and `stmt.rs` from the node descriptions below.
+///
+/// Each description is `Name : Type field, ...`. `Expr` and `Stmt` fields are
+/// boxed, a trailing `?` makes them optional, and an `Id` field is filled in
+/// from `next_id()` instead of being passed to the constructor. Statement
+/// nodes are shared through `Rc`, so generators can keep the statements they
+/// are suspended in without copying them.
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 1 {
-        println!("Usage: generate_ast <output directory>");
-        std::process::exit(1);
+    if args.len() != 2 {
+        println!("Usage: gen_ast <output directory>");
+        std::process::exit(64);
     }
-    // let output_dir = args[0];
-    // define_ast(output_dir, "Expr",
+    let output_dir = Path::new(&args[1]);
+    let result = define_ast(
+        output_dir,
+        "Expr",
+        false,
+        &["crate::pattern::MatchArm", "crate::token::Token", "crate::tokentype::Literals"],
+        &[
+            "Binary   : Expr left, Token operator, Expr right",
+            "Grouping : Expr expression",
+            "Literal  : Literals value",
+            "Unary    : Token operator, Expr right",
+            "Variable : Id id, Token name",
+            "Assign   : Id id, Token name, Expr value",
+            "Logical  : Expr left, Token operator, Expr right",
+            "Call     : Expr callee, Token paren, Vec<Expr> arguments, Vec<Token> names",
+            "Get      : Expr object, Token name, bool optional",
+            "Set      : Expr object, Token name, Expr value",
+            "This     : Id id, Token keyword",
+            "Conditional : Expr condition, Token question, Expr then_branch, Expr else_branch",
+            "List     : Token bracket, Vec<Expr> elements",
+            "Map      : Token brace, Vec<Expr> keys, Vec<Expr> values",
+            "Index    : Expr object, Token bracket, Expr index",
+            "SetIndex : Expr object, Token bracket, Expr index, Expr value",
+            "Match    : Token keyword, Expr value, Vec<MatchArm> arms",
+        ],
+    )
+    .and_then(|_| {
+        define_ast(
+            output_dir,
+            "Stmt",
+            true,
+            &["crate::expr::Expr", "crate::token::Token", "crate::tokentype::FunctionKind", "std::rc::Rc"],
+            &[
+                "Expression : Expr expression",
+                "Print      : Token keyword, Expr expression",
+                "Var        : Token name, Expr? initializer, bool constant",
+                "Block      : Vec<Stmt> statements",
+                "If         : Token token, Expr condition, Stmt then_branch, Stmt? else_branch",
+                "While      : Token token, Expr condition, Stmt body",
+                "For        : Token token, Stmt? initializer, Expr? condition, Expr? increment, Stmt body",
+                "ForIn      : Token token, Token name, Expr iterable, Stmt body",
+                "Break      : Token keyword",
+                "Continue   : Token keyword",
+                "Function   : Token name, FunctionKind kind, Vec<Token> params, Vec<Option<Expr>> defaults, Option<Token> rest, Vec<Stmt> body",
+                "Return     : Token keyword, Expr? value",
+                "Yield      : Token keyword, Expr value",
+                "Class      : Token name, Vec<Expr> traits, Vec<Function> methods",
+                "Trait      : Token name, Vec<Function> methods",
+                "Enum       : Token name, Vec<Token> variants",
+                "Throw      : Token keyword, Expr value",
+                "Import     : Token keyword, Token path, Option<Token> name, Vec<Token> names",
+                "Try        : Token keyword, Vec<Stmt> body, Option<Token> name, Vec<Stmt> catch_body, Option<Vec<Stmt>> finally_body",
+            ],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("gen_ast: {}", e);
+        std::process::exit(74);
+    }
+}
+
+struct Field {
+    name: String,
+    kind: FieldKind,
+}
+
+enum FieldKind {
+    /// A child node, stored as `Box<Expr>` or `Box<Stmt>`.
+    Node(String),
+    /// An optional child node, stored as `Option<Box<..>>`.
+    OptionalNode(String),
+    /// A resolver id assigned by the constructor.
+    Id,
+    /// Any other type, stored as written.
+    Plain(String),
 }
 
-/*
-fn define_ast(output_dir: String, base_name: String, types: Vec<String>) {
-    let path = output_dir + "/" + base_name + ".rs";
-    let f = File::create(path).expect("Unable to create file!");
-    let mut writer = BufWriter::new(f);
+impl Field {
+    fn parse(spec: &str) -> Field {
+        let mut parts = spec.split_whitespace();
+        let ty = parts.next().expect("missing field type");
+        let name = parts.next().expect("missing field name").to_string();
+        let kind = match ty {
+            "Id" => FieldKind::Id,
+            "Expr" | "Stmt" => FieldKind::Node(ty.to_string()),
+            "Expr?" | "Stmt?" => FieldKind::OptionalNode(ty.trim_end_matches('?').to_string()),
+            _ => FieldKind::Plain(ty.to_string()),
+        };
+        Field { name, kind }
+    }
 
-    writer.write("use std;\n");
+    fn storage_type(&self) -> String {
+        match &self.kind {
+            FieldKind::Node(ty) => format!("Box<{}>", ty),
+            FieldKind::OptionalNode(ty) => format!("Option<Box<{}>>", ty),
+            FieldKind::Id => "usize".to_string(),
+            FieldKind::Plain(ty) => ty.clone(),
+        }
+    }
+
+    fn param(&self) -> Option<String> {
+        match &self.kind {
+            FieldKind::Node(ty) | FieldKind::Plain(ty) => Some(format!("{}: {}", self.name, ty)),
+            FieldKind::OptionalNode(ty) => Some(format!("{}: Option<{}>", self.name, ty)),
+            FieldKind::Id => None,
+        }
+    }
+
+    fn initializer(&self) -> String {
+        match &self.kind {
+            FieldKind::Node(_) => format!("{}: Box::new({})", self.name, self.name),
+            FieldKind::OptionalNode(_) => format!("{}: {}.map(Box::new)", self.name, self.name),
+            FieldKind::Id => format!("{}: next_id()", self.name),
+            FieldKind::Plain(_) => self.name.clone(),
+        }
+    }
+}
+
+struct Type {
+    name: String,
+    fields: Vec<Field>,
+}
+
+fn parse_type(description: &str) -> Type {
+    let mut split = description.splitn(2, ':');
+    let name = split.next().unwrap().trim().to_string();
+    let fields = split
+        .next()
+        .expect("missing ':' in node description")
+        .split(',')
+        .map(Field::parse)
+        .collect();
+    Type { name, fields }
+}
+
+fn define_ast(
+    output_dir: &Path,
+    base_name: &str,
+    shared: bool,
+    imports: &[&str],
+    descriptions: &[&str],
+) -> io::Result<()> {
+    let types: Vec<Type> = descriptions.iter().map(|d| parse_type(d)).collect();
+    let source = render(base_name, shared, imports, &types);
+    let path = output_dir.join(format!("{}.rs", base_name.to_lowercase()));
+    fs::write(&path, rustfmt(&source)?)
+}
+
+fn render(base_name: &str, shared: bool, imports: &[&str], types: &[Type]) -> String {
+    let param = base_name.to_lowercase();
+    let mut out = String::new();
+    out.push_str("// Generated by src/bin/gen_ast.rs; edit the node descriptions there.\n");
+    for import in imports {
+        writeln!(out, "use {};", import).unwrap();
+    }
+
+    let has_ids = types
+        .iter()
+        .any(|t| t.fields.iter().any(|f| matches!(f.kind, FieldKind::Id)));
+    if has_ids {
+        out.push_str(concat!(
+            "use std::sync::atomic::{AtomicUsize, Ordering};\n\n",
+            "static NEXT_ID: AtomicUsize = AtomicUsize::new(0);\n\n",
+            "/// Unique id for nodes the `Resolver` records in the interpreter's side\n",
+            "/// table, so resolutions from separately scanned sources never collide.\n",
+            "fn next_id() -> usize {\n",
+            "    NEXT_ID.fetch_add(1, Ordering::Relaxed)\n",
+            "}\n",
+        ));
+    }
+
+    writeln!(out, "\n#[derive(Debug, Clone)]\npub enum {} {{", base_name).unwrap();
+    for t in types {
+        if shared {
+            writeln!(out, "{}(Rc<{}>),", t.name, t.name).unwrap();
+        } else {
+            writeln!(out, "{}({}),", t.name, t.name).unwrap();
+        }
+    }
+    out.push_str("}\n");
+
+    for t in types {
+        writeln!(out, "\n#[derive(Debug, Clone)]\npub struct {} {{", t.name).unwrap();
+        for f in &t.fields {
+            writeln!(out, "pub {}: {},", f.name, f.storage_type()).unwrap();
+        }
+        out.push_str("}\n");
+    }
 
     for t in types {
-        let t_split = t.split(":");
-        assert!(t_split.len, 2);
-        let struct_name = t_split[0].trim();
-        let fields = t_split[1].trim();
-        define_type(writer, base_name, struct_name, fields);
+        let params: Vec<String> = t.fields.iter().filter_map(Field::param).collect();
+        let inits: Vec<String> = t.fields.iter().map(Field::initializer).collect();
+        let node = format!("{} {{ {} }}", t.name, inits.join(", "));
+        let node = if shared { format!("Rc::new({})", node) } else { node };
+        writeln!(
+            out,
+            "\nimpl {name} {{\npub fn new({params}) -> {base} {{\n{base}::{name}({node})\n}}\n}}",
+            name = t.name,
+            base = base_name,
+            params = params.join(", "),
+            node = node,
+        )
+        .unwrap();
     }
+
+    writeln!(
+        out,
+        "\nimpl {} {{\npub fn accept<T, V: Visitor<T>>(&self, visitor: &mut V) -> T {{\nmatch self {{",
+        base_name
+    )
+    .unwrap();
+    for t in types {
+        writeln!(
+            out,
+            "{}::{}(e) => visitor.visit_{}_{}(e),",
+            base_name,
+            t.name,
+            snake_case(&t.name),
+            param
+        )
+        .unwrap();
+    }
+    out.push_str("}\n}\n}\n");
+
+    out.push_str("\npub trait Visitor<T> {\n");
+    for t in types {
+        writeln!(
+            out,
+            "fn visit_{}_{}(&mut self, {}: &{}) -> T;",
+            snake_case(&t.name),
+            param,
+            param,
+            t.name
+        )
+        .unwrap();
+    }
+    out.push_str("}\n");
+    out
 }
 
-fn define_type<W: Write>(
-    writer: W, base_name: String,
-    struct_name: String, fields: String) {
-    writer.write_fmt("struct {} \{\n", struct_name);
+/// `SetIndex` -> `set_index`, for visitor method names.
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
 
+/// Lays the generated source out the same way `cargo fmt` does for the rest
+/// of the crate.
+fn rustfmt(source: &str) -> io::Result<String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2018", "--emit", "stdout", "--quiet"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(source.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("rustfmt failed"));
+    }
+    Ok(String::from_utf8(output.stdout).unwrap())
 }
-*/
@@ -0,0 +1,504 @@
+use crate::expr::{
+    self, Assign, Binary, Call, Conditional, Get, Grouping, Index, List, Literal, Logical, Map, Match, Set, SetIndex, This, Unary,
+    Variable,
+};
+use crate::pattern::Pattern;
+use crate::stmt::{self, Block, Break, Class, Continue, Enum, Expression, For, ForIn, Function, If, Import, Print, Return, Stmt, Throw, Trait, Try, Var, While, Yield};
+use crate::token::Token;
+use crate::tokentype::Literals;
+use std::fmt::Write;
+
+/// A structured view of tokens and syntax trees that can be written out as
+/// S-expressions or JSON for tools and snapshot tests.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    NULL,
+    BOOL(bool),
+    NUMBER(f64),
+    INTEGER(i64),
+    STRING(String),
+    /// A bare name, such as an identifier or operator lexeme.
+    SYMBOL(String),
+    LIST(Vec<Value>),
+    NODE(&'static str, Vec<(&'static str, Value)>),
+}
+
+/// Dumps the token stream as a JSON array with one token per line.
+pub fn tokens_json(tokens: &[Token]) -> String {
+    let mut out = String::from("[\n");
+    for (i, token) in tokens.iter().enumerate() {
+        let literal = match &token.literal {
+            Some(literal) => literal_value(literal),
+            None => Value::NULL,
+        };
+        out.push_str("  {\"type\": ");
+        write_quoted(&mut out, &format!("{:?}", token.token_type));
+        out.push_str(", \"lexeme\": ");
+        write_quoted(&mut out, &token.lexeme);
+        out.push_str(", \"literal\": ");
+        literal.write_json(&mut out, None);
+        write!(out, ", \"line\": {}}}", token.line).unwrap();
+        if i + 1 < tokens.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Dumps each statement as an S-expression on its own line.
+pub fn ast_sexpr(statements: &[Stmt]) -> String {
+    let mut out = String::new();
+    for statement in statements {
+        statement.accept(&mut AstDumper).write_sexpr(&mut out);
+        out.push('\n');
+    }
+    out
+}
+
+/// Dumps the program as an indented JSON array of statement objects.
+pub fn ast_json(statements: &[Stmt]) -> String {
+    let program = Value::LIST(statements.iter().map(|s| s.accept(&mut AstDumper)).collect());
+    let mut out = String::new();
+    program.write_json(&mut out, Some(0));
+    out.push('\n');
+    out
+}
+
+fn literal_value(literal: &Literals) -> Value {
+    match literal {
+        Literals::NUMBER(n) => Value::NUMBER(*n),
+        Literals::INTEGER(n) => Value::INTEGER(*n),
+        Literals::STRING(s) => Value::STRING(s.clone()),
+        Literals::BOOL(b) => Value::BOOL(*b),
+        Literals::NIL(_) => Value::NULL,
+    }
+}
+
+impl Value {
+    pub fn write_sexpr(&self, out: &mut String) {
+        match self {
+            Value::NULL => out.push_str("nil"),
+            Value::BOOL(b) => write!(out, "{}", b).unwrap(),
+            Value::NUMBER(n) => write!(out, "{}", n).unwrap(),
+            Value::INTEGER(n) => write!(out, "{}", n).unwrap(),
+            Value::STRING(s) => write_quoted(out, s),
+            Value::SYMBOL(s) => out.push_str(s),
+            Value::LIST(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(' ');
+                    }
+                    item.write_sexpr(out);
+                }
+                out.push(']');
+            }
+            Value::NODE(name, fields) => {
+                out.push('(');
+                out.push_str(&name.to_lowercase());
+                for (_, field) in fields {
+                    out.push(' ');
+                    field.write_sexpr(out);
+                }
+                out.push(')');
+            }
+        }
+    }
+
+    /// Writes compact JSON when `indent` is `None`, otherwise one member per
+    /// line starting at the given depth.
+    pub fn write_json(&self, out: &mut String, indent: Option<usize>) {
+        match self {
+            Value::NULL => out.push_str("null"),
+            Value::BOOL(b) => write!(out, "{}", b).unwrap(),
+            Value::NUMBER(n) => write!(out, "{}", n).unwrap(),
+            Value::INTEGER(n) => write!(out, "{}", n).unwrap(),
+            Value::STRING(s) | Value::SYMBOL(s) => write_quoted(out, s),
+            Value::LIST(items) => {
+                if items.is_empty() {
+                    out.push_str("[]");
+                    return;
+                }
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    json_break(out, indent.map(|d| d + 1));
+                    item.write_json(out, indent.map(|d| d + 1));
+                }
+                json_break(out, indent);
+                out.push(']');
+            }
+            Value::NODE(name, fields) => {
+                out.push('{');
+                json_break(out, indent.map(|d| d + 1));
+                write!(out, "\"node\": \"{}\"", name).unwrap();
+                for (key, field) in fields {
+                    out.push(',');
+                    json_break(out, indent.map(|d| d + 1));
+                    write!(out, "\"{}\": ", key).unwrap();
+                    field.write_json(out, indent.map(|d| d + 1));
+                }
+                json_break(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+/// Starts the next member on its own line, or separates members with a
+/// space in compact output.
+fn json_break(out: &mut String, indent: Option<usize>) {
+    match indent {
+        Some(depth) => {
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+        }
+        None => {
+            if out.ends_with(',') {
+                out.push(' ');
+            }
+        }
+    }
+}
+
+fn write_quoted(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn symbol(token: &Token) -> Value {
+    Value::SYMBOL(token.lexeme.clone())
+}
+
+fn optional<T, F: FnOnce(&T) -> Value>(value: &Option<T>, f: F) -> Value {
+    value.as_ref().map(f).unwrap_or(Value::NULL)
+}
+
+/// Builds a `Value` tree with one node per `Expr` or `Stmt`, keyed by the
+/// node's field names.
+struct AstDumper;
+
+impl AstDumper {
+    fn pattern(&mut self, pattern: &Pattern) -> Value {
+        match pattern {
+            Pattern::WILDCARD(_) => Value::NODE("Wildcard", vec![]),
+            Pattern::LITERAL(literal) => literal.accept(self),
+            Pattern::BINDING(name) => Value::NODE("Binding", vec![("name", symbol(name))]),
+            Pattern::ALTERNATIVE(alternatives) => Value::NODE(
+                "Alternative",
+                vec![("patterns", Value::LIST(alternatives.iter().map(|p| self.pattern(p)).collect()))],
+            ),
+            Pattern::INSTANCE(class, fields) => Value::NODE(
+                "Instance",
+                vec![
+                    ("class", class.accept(self)),
+                    (
+                        "fields",
+                        Value::LIST(
+                            fields
+                                .iter()
+                                .map(|(name, field)| {
+                                    Value::NODE("Field", vec![("name", symbol(name)), ("pattern", self.pattern(field))])
+                                })
+                                .collect(),
+                        ),
+                    ),
+                ],
+            ),
+        }
+    }
+    fn function(&mut self, function: &Function) -> Value {
+        Value::NODE(
+            "Function",
+            vec![
+                ("name", symbol(&function.name)),
+                ("kind", Value::SYMBOL(format!("{:?}", function.kind).to_lowercase())),
+                ("params", Value::LIST(function.params.iter().map(symbol).collect())),
+                (
+                    "defaults",
+                    Value::LIST(function.defaults.iter().map(|d| optional(d, |e| e.accept(self))).collect()),
+                ),
+                ("rest", optional(&function.rest, symbol)),
+                ("body", self.statements(&function.body)),
+            ],
+        )
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) -> Value {
+        Value::LIST(statements.iter().map(|s| s.accept(self)).collect())
+    }
+}
+
+impl expr::Visitor<Value> for AstDumper {
+    fn visit_binary_expr(&mut self, expr: &Binary) -> Value {
+        Value::NODE(
+            "Binary",
+            vec![
+                ("operator", symbol(&expr.operator)),
+                ("left", expr.left.accept(self)),
+                ("right", expr.right.accept(self)),
+            ],
+        )
+    }
+    fn visit_grouping_expr(&mut self, expr: &Grouping) -> Value {
+        Value::NODE("Grouping", vec![("expression", expr.expression.accept(self))])
+    }
+    fn visit_literal_expr(&mut self, expr: &Literal) -> Value {
+        Value::NODE("Literal", vec![("value", literal_value(&expr.value))])
+    }
+    fn visit_unary_expr(&mut self, expr: &Unary) -> Value {
+        Value::NODE(
+            "Unary",
+            vec![("operator", symbol(&expr.operator)), ("right", expr.right.accept(self))],
+        )
+    }
+    fn visit_variable_expr(&mut self, expr: &Variable) -> Value {
+        Value::NODE("Variable", vec![("name", symbol(&expr.name))])
+    }
+    fn visit_assign_expr(&mut self, expr: &Assign) -> Value {
+        Value::NODE(
+            "Assign",
+            vec![("name", symbol(&expr.name)), ("value", expr.value.accept(self))],
+        )
+    }
+    fn visit_logical_expr(&mut self, expr: &Logical) -> Value {
+        Value::NODE(
+            "Logical",
+            vec![
+                ("operator", symbol(&expr.operator)),
+                ("left", expr.left.accept(self)),
+                ("right", expr.right.accept(self)),
+            ],
+        )
+    }
+    fn visit_call_expr(&mut self, expr: &Call) -> Value {
+        Value::NODE(
+            "Call",
+            vec![
+                ("callee", expr.callee.accept(self)),
+                ("arguments", Value::LIST(expr.arguments.iter().map(|a| a.accept(self)).collect())),
+                ("names", Value::LIST(expr.names.iter().map(symbol).collect())),
+            ],
+        )
+    }
+    fn visit_get_expr(&mut self, expr: &Get) -> Value {
+        Value::NODE(
+            "Get",
+            vec![
+                ("object", expr.object.accept(self)),
+                ("name", symbol(&expr.name)),
+                ("optional", Value::BOOL(expr.optional)),
+            ],
+        )
+    }
+    fn visit_set_expr(&mut self, expr: &Set) -> Value {
+        Value::NODE(
+            "Set",
+            vec![
+                ("object", expr.object.accept(self)),
+                ("name", symbol(&expr.name)),
+                ("value", expr.value.accept(self)),
+            ],
+        )
+    }
+    fn visit_this_expr(&mut self, _expr: &This) -> Value {
+        Value::NODE("This", vec![])
+    }
+    fn visit_list_expr(&mut self, expr: &List) -> Value {
+        Value::NODE(
+            "List",
+            vec![("elements", Value::LIST(expr.elements.iter().map(|e| e.accept(self)).collect()))],
+        )
+    }
+    fn visit_map_expr(&mut self, expr: &Map) -> Value {
+        Value::NODE(
+            "Map",
+            vec![
+                ("keys", Value::LIST(expr.keys.iter().map(|k| k.accept(self)).collect())),
+                ("values", Value::LIST(expr.values.iter().map(|v| v.accept(self)).collect())),
+            ],
+        )
+    }
+    fn visit_index_expr(&mut self, expr: &Index) -> Value {
+        Value::NODE(
+            "Index",
+            vec![("object", expr.object.accept(self)), ("index", expr.index.accept(self))],
+        )
+    }
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> Value {
+        Value::NODE(
+            "SetIndex",
+            vec![
+                ("object", expr.object.accept(self)),
+                ("index", expr.index.accept(self)),
+                ("value", expr.value.accept(self)),
+            ],
+        )
+    }
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> Value {
+        Value::NODE(
+            "Conditional",
+            vec![
+                ("condition", expr.condition.accept(self)),
+                ("then_branch", expr.then_branch.accept(self)),
+                ("else_branch", expr.else_branch.accept(self)),
+            ],
+        )
+    }
+    fn visit_match_expr(&mut self, expr: &Match) -> Value {
+        let arms = expr
+            .arms
+            .iter()
+            .map(|arm| Value::NODE("Arm", vec![("pattern", self.pattern(&arm.pattern)), ("body", arm.body.accept(self))]))
+            .collect();
+        Value::NODE("Match", vec![("value", expr.value.accept(self)), ("arms", Value::LIST(arms))])
+    }
+}
+
+impl stmt::Visitor<Value> for AstDumper {
+    fn visit_expression_stmt(&mut self, stmt: &Expression) -> Value {
+        Value::NODE("Expression", vec![("expression", stmt.expression.accept(self))])
+    }
+    fn visit_print_stmt(&mut self, stmt: &Print) -> Value {
+        Value::NODE("Print", vec![("expression", stmt.expression.accept(self))])
+    }
+    fn visit_var_stmt(&mut self, stmt: &Var) -> Value {
+        Value::NODE(
+            if stmt.constant { "Const" } else { "Var" },
+            vec![
+                ("name", symbol(&stmt.name)),
+                ("initializer", optional(&stmt.initializer, |e| e.accept(self))),
+            ],
+        )
+    }
+    fn visit_block_stmt(&mut self, stmt: &Block) -> Value {
+        Value::NODE("Block", vec![("statements", self.statements(&stmt.statements))])
+    }
+    fn visit_if_stmt(&mut self, stmt: &If) -> Value {
+        Value::NODE(
+            "If",
+            vec![
+                ("condition", stmt.condition.accept(self)),
+                ("then_branch", stmt.then_branch.accept(self)),
+                ("else_branch", optional(&stmt.else_branch, |s| s.accept(self))),
+            ],
+        )
+    }
+    fn visit_while_stmt(&mut self, stmt: &While) -> Value {
+        Value::NODE(
+            "While",
+            vec![("condition", stmt.condition.accept(self)), ("body", stmt.body.accept(self))],
+        )
+    }
+    fn visit_for_stmt(&mut self, stmt: &For) -> Value {
+        let initializer = optional(&stmt.initializer, |s| s.accept(self));
+        let condition = optional(&stmt.condition, |e| e.accept(self));
+        let increment = optional(&stmt.increment, |e| e.accept(self));
+        Value::NODE(
+            "For",
+            vec![
+                ("initializer", initializer),
+                ("condition", condition),
+                ("increment", increment),
+                ("body", stmt.body.accept(self)),
+            ],
+        )
+    }
+    fn visit_function_stmt(&mut self, stmt: &Function) -> Value {
+        self.function(stmt)
+    }
+    fn visit_return_stmt(&mut self, stmt: &Return) -> Value {
+        Value::NODE("Return", vec![("value", optional(&stmt.value, |e| e.accept(self)))])
+    }
+    fn visit_yield_stmt(&mut self, stmt: &Yield) -> Value {
+        Value::NODE("Yield", vec![("value", stmt.value.accept(self))])
+    }
+    fn visit_import_stmt(&mut self, stmt: &Import) -> Value {
+        let path = match &stmt.path.literal {
+            Some(literal) => literal_value(literal),
+            None => Value::NULL,
+        };
+        Value::NODE(
+            "Import",
+            vec![
+                ("path", path),
+                ("name", optional(&stmt.name, symbol)),
+                ("names", Value::LIST(stmt.names.iter().map(symbol).collect())),
+            ],
+        )
+    }
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> Value {
+        Value::NODE(
+            "ForIn",
+            vec![
+                ("name", symbol(&stmt.name)),
+                ("iterable", stmt.iterable.accept(self)),
+                ("body", stmt.body.accept(self)),
+            ],
+        )
+    }
+    fn visit_break_stmt(&mut self, _stmt: &Break) -> Value {
+        Value::NODE("Break", vec![])
+    }
+    fn visit_continue_stmt(&mut self, _stmt: &Continue) -> Value {
+        Value::NODE("Continue", vec![])
+    }
+    fn visit_throw_stmt(&mut self, stmt: &Throw) -> Value {
+        Value::NODE("Throw", vec![("value", stmt.value.accept(self))])
+    }
+    fn visit_try_stmt(&mut self, stmt: &Try) -> Value {
+        let finally_body = optional(&stmt.finally_body, |body| self.statements(body));
+        Value::NODE(
+            "Try",
+            vec![
+                ("body", self.statements(&stmt.body)),
+                ("name", optional(&stmt.name, symbol)),
+                ("catch_body", self.statements(&stmt.catch_body)),
+                ("finally_body", finally_body),
+            ],
+        )
+    }
+    fn visit_class_stmt(&mut self, stmt: &Class) -> Value {
+        Value::NODE(
+            "Class",
+            vec![
+                ("name", symbol(&stmt.name)),
+                ("traits", Value::LIST(stmt.traits.iter().map(|t| t.accept(self)).collect())),
+                ("methods", Value::LIST(stmt.methods.iter().map(|m| self.function(m)).collect())),
+            ],
+        )
+    }
+    fn visit_trait_stmt(&mut self, stmt: &Trait) -> Value {
+        Value::NODE(
+            "Trait",
+            vec![
+                ("name", symbol(&stmt.name)),
+                ("methods", Value::LIST(stmt.methods.iter().map(|m| self.function(m)).collect())),
+            ],
+        )
+    }
+    fn visit_enum_stmt(&mut self, stmt: &Enum) -> Value {
+        Value::NODE(
+            "Enum",
+            vec![
+                ("name", symbol(&stmt.name)),
+                ("variants", Value::LIST(stmt.variants.iter().map(symbol).collect())),
+            ],
+        )
+    }
+}
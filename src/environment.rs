@@ -1,16 +1,25 @@
 use crate::interpreter::{RTResult, RuntimeException};
 use crate::object::Object;
 use crate::token::Token;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 // use std::borrow::{Borrow, BorrowMut};
 use gc::{Gc, GcCell};
 use gc_derive::{Finalize, Trace};
 use std::ops::Deref;
 
+/// A single scope frame.
+///
+/// Globals are looked up by name in `values`. Every other scope stores its
+/// locals in `slots`, indexed by the slot the `Resolver` assigned when the
+/// name was declared; since declarations are executed in the same order they
+/// were resolved, `define` simply pushes the next slot.
 #[derive(Trace, Finalize, Debug)]
 pub struct Env {
     enclosing: Option<Environment>,
+    slots: Vec<Object>,
     values: HashMap<String, Object>,
+    /// Globals declared with `const`.
+    constants: HashSet<String>,
 }
 
 type GcEnv = Gc<GcCell<Env>>;
@@ -28,12 +37,20 @@ impl Deref for Environment {
     }
 }
 
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
+
 impl Environment {
     pub fn new() -> Environment {
         Environment {
             env: Gc::new(GcCell::new(Env {
                 enclosing: None,
+                slots: Vec::new(),
                 values: HashMap::new(),
+                constants: HashSet::new(),
             })),
         }
     }
@@ -41,108 +58,121 @@ impl Environment {
         Environment {
             env: Gc::new(GcCell::new(Env {
                 enclosing: Some(env),
+                slots: Vec::new(),
                 values: HashMap::new(),
+                constants: HashSet::new(),
             })),
         }
     }
 
-    pub fn get_enclosing(&self) -> Option<Environment> {
-        match &self.borrow().enclosing {
-            Some(env) => Some(env.clone()),
-            None => None,
-        }
-    }
-
     /*
     pub fn take_enclosing(&mut self) -> Box<Environment> {
         self.enclosing.take().or(Some(Environment::new())).unwrap()
     }
     */
 
+    /// Redefining a global replaces it.
     pub fn define(&mut self, name: String, value: Object) {
-        self.borrow_mut().values.insert(name, value);
+        self.define_slot(None, name, value)
+    }
+
+    /// Like `define`, for a local the resolver gave `slot`. Locals are only
+    /// ever pushed, so that must be the next free slot.
+    pub fn define_slot(&mut self, slot: Option<usize>, name: String, value: Object) {
+        let mut env = self.borrow_mut();
+        if env.enclosing.is_some() {
+            debug_assert!(
+                slot.is_none_or(|slot| slot == env.slots.len()),
+                "'{}' resolved to slot {:?} but defined in slot {}",
+                name,
+                slot,
+                env.slots.len()
+            );
+            env.slots.push(value);
+        } else {
+            env.values.insert(name, value);
+        }
+    }
+
+    /// Defines the name of a declaration, which may not replace a global
+    /// constant.
+    pub fn declare(&mut self, name: &Token, slot: Option<usize>, value: Object) -> RTResult {
+        if self.borrow().constants.contains(&name.lexeme) {
+            return Err(RuntimeException::error(
+                name,
+                format!("Cannot redefine constant '{}'.", name.lexeme).as_str(),
+            ));
+        }
+        self.define_slot(slot, name.lexeme.clone(), value);
+        Ok(Object::NIL())
+    }
+
+    /// Declares a `const`; the resolver already rejects assignments to
+    /// local constants.
+    pub fn declare_constant(&mut self, name: &Token, slot: Option<usize>, value: Object) -> RTResult {
+        self.declare(name, slot, value)?;
+        let mut env = self.borrow_mut();
+        if env.enclosing.is_none() {
+            env.constants.insert(name.lexeme.clone());
+        }
+        Ok(Object::NIL())
+    }
+
+    /// The outermost scope of this chain: the globals of the file it belongs to.
+    pub fn root(&self) -> Environment {
+        let mut environment = self.clone();
+        loop {
+            let enclosing = environment.borrow().enclosing.clone();
+            match enclosing {
+                Some(enclosing) => environment = enclosing,
+                None => return environment,
+            }
+        }
     }
 
     pub fn get(&self, name: &Token) -> RTResult {
         match self.borrow().values.get(&name.lexeme) {
             Some(v) => Ok(v.clone()),
-            None => {
-                if self.borrow().enclosing.is_some() {
-                    self.borrow().enclosing.as_ref().unwrap().get(name)
-                } else {
-                    Err(RuntimeException::error(
-                        &name,
-                        format!("Undefined variable '{}'.", name.lexeme).as_str(),
-                    ))
-                }
-            }
+            None => Err(RuntimeException::error(
+                name,
+                format!("Undefined variable '{}'.", name.lexeme).as_str(),
+            )),
         }
     }
 
-    pub fn get_at(&self, distance: usize, name: &String) -> RTResult {
-        // println!("{} distance: {}", name, distance);
-        // println!("{:?}", self);
-        if distance == 0 {
-            // 可能是bug, 打印 hashmap 出错
-            // println!("{:?}", self.values);
-            Ok(self.borrow().values.get(name).unwrap().clone())
-        } else {
-            Ok(self
-                .ancestor(distance)
-                .borrow()
-                .values
-                .get(name)
-                .unwrap()
-                .clone()
-            )
-        }
+    pub fn get_at(&self, distance: usize, slot: usize) -> RTResult {
+        Ok(self.ancestor(distance).borrow().slots[slot].clone())
     }
 
     fn ancestor(&self, distance: usize) -> Environment {
-        let mut environment = self.borrow().enclosing.as_ref().unwrap().clone();
-        for _ in 1..distance {
-            environment = environment
-                .clone()
-                .borrow()
-                .enclosing
-                .as_ref()
-                .unwrap()
-                .clone();
+        let mut environment = self.clone();
+        for _ in 0..distance {
+            let enclosing = environment.borrow().enclosing.as_ref().unwrap().clone();
+            environment = enclosing;
         }
         environment
     }
 
     pub fn assign(&mut self, name: &Token, value: Object) -> RTResult {
-        if self.borrow().values.contains_key(&name.lexeme) {
-            self.borrow_mut()
-                .values
-                .insert(name.lexeme.clone(), value.clone());
+        let mut env = self.borrow_mut();
+        if env.constants.contains(&name.lexeme) {
+            Err(RuntimeException::error(
+                name,
+                format!("Cannot assign to constant '{}'.", name.lexeme).as_str(),
+            ))
+        } else if env.values.contains_key(&name.lexeme) {
+            env.values.insert(name.lexeme.clone(), value.clone());
             Ok(value)
-        } else if self.borrow().enclosing.is_some() {
-            self.borrow_mut()
-                .enclosing
-                .as_mut()
-                .unwrap()
-                .assign(name, value)
         } else {
             Err(RuntimeException::error(
-                &name,
+                name,
                 format!("Undefined variable '{}'.", name.lexeme).as_str(),
             ))
         }
     }
 
-    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Object) -> RTResult {
-        if distance == 0 {
-            self.borrow_mut()
-                .values
-                .insert(name.lexeme.clone(), value.clone());
-        } else {
-            self.ancestor(distance)
-                .borrow_mut()
-                .values
-                .insert(name.lexeme.clone(), value.clone());
-        }
+    pub fn assign_at(&mut self, distance: usize, slot: usize, value: Object) -> RTResult {
+        self.ancestor(distance).borrow_mut().slots[slot] = value.clone();
         Ok(value)
     }
 }
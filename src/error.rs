@@ -1,8 +1,53 @@
+use crate::interpreter::RuntimeError;
 use crate::token::Token;
 use crate::tokentype::TokenType;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// A shared diagnostics stream.
+pub type Sink = Rc<RefCell<dyn Write>>;
+
+thread_local!(static HAD_ERROR: Cell<bool> = const { Cell::new(false) });
+thread_local!(static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) });
+
+/// Sends scan, parse and resolve diagnostics on this thread to `sink`
+/// instead of standard error until the returned guard is dropped.
+pub fn redirect(sink: Sink) -> Redirect {
+    Redirect(SINK.with(|s| s.replace(Some(sink))))
+}
+
+pub struct Redirect(Option<Sink>);
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        SINK.with(|s| *s.borrow_mut() = self.0.take());
+    }
+}
+
+fn emit(message: fmt::Arguments) {
+    SINK.with(|sink| match &*sink.borrow() {
+        Some(sink) => {
+            let _ = writeln!(sink.borrow_mut(), "{}", message);
+        }
+        None => eprintln!("{}", message),
+    });
+}
+
+/// Whether a scan, parse or resolve error was reported since the last
+/// `reset`; the program must not be run if so.
+pub fn had_error() -> bool {
+    HAD_ERROR.with(|e| e.get())
+}
+
+pub fn reset() {
+    HAD_ERROR.with(|e| e.set(false));
+}
 
 fn report(line: u64, w: &str, message: &str) {
-    println!("[line {}] Error{}: {}", line, w, message);
+    HAD_ERROR.with(|e| e.set(true));
+    emit(format_args!("[line {}] Error{}: {}", line, w, message));
 }
 
 pub fn error(line: u64, message: &str) {
@@ -20,3 +65,27 @@ pub fn parse_error(token: &Token, message: &str) {
         }
     }
 }
+
+/// Reports a problem that does not stop the program from running.
+pub fn warning(token: &Token, message: &str) {
+    emit(format_args!("[line {}] Warning at '{}': {}", token.line, token.lexeme, message));
+}
+
+pub fn runtime_error(out: &mut dyn Write, error: &RuntimeError) -> io::Result<()> {
+    writeln!(out, "{}", error.message)?;
+    // Runaway recursion produces thousands of identical frames; print each
+    // run of repeats once, like Python does.
+    let mut frames = error.backtrace.iter().map(|frame| frame.to_string()).peekable();
+    while let Some(frame) = frames.next() {
+        writeln!(out, "{}", frame)?;
+        let mut repeated = 0;
+        while frames.peek() == Some(&frame) {
+            frames.next();
+            repeated += 1;
+        }
+        if repeated > 0 {
+            writeln!(out, "[previous line repeated {} more times]", repeated)?;
+        }
+    }
+    Ok(())
+}
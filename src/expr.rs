@@ -1,5 +1,16 @@
+// Generated by src/bin/gen_ast.rs; edit the node descriptions there.
+use crate::pattern::MatchArm;
 use crate::token::Token;
 use crate::tokentype::Literals;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Unique id for nodes the `Resolver` records in the interpreter's side
+/// table, so resolutions from separately scanned sources never collide.
+fn next_id() -> usize {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
 
 #[derive(Debug, Clone)]
 pub enum Expr {
@@ -14,6 +25,12 @@ pub enum Expr {
     Get(Get),
     Set(Set),
     This(This),
+    Conditional(Conditional),
+    List(List),
+    Map(Map),
+    Index(Index),
+    SetIndex(SetIndex),
+    Match(Match),
 }
 
 #[derive(Debug, Clone)]
@@ -41,11 +58,13 @@ pub struct Unary {
 
 #[derive(Debug, Clone)]
 pub struct Variable {
+    pub id: usize,
     pub name: Token,
 }
 
 #[derive(Debug, Clone)]
 pub struct Assign {
+    pub id: usize,
     pub name: Token,
     pub value: Box<Expr>,
 }
@@ -62,12 +81,14 @@ pub struct Call {
     pub callee: Box<Expr>,
     pub paren: Token,
     pub arguments: Vec<Expr>,
+    pub names: Vec<Token>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Get {
     pub object: Box<Expr>,
     pub name: Token,
+    pub optional: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -79,7 +100,51 @@ pub struct Set {
 
 #[derive(Debug, Clone)]
 pub struct This {
+    pub id: usize,
+    pub keyword: Token,
+}
+
+#[derive(Debug, Clone)]
+pub struct Conditional {
+    pub condition: Box<Expr>,
+    pub question: Token,
+    pub then_branch: Box<Expr>,
+    pub else_branch: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct List {
+    pub bracket: Token,
+    pub elements: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Map {
+    pub brace: Token,
+    pub keys: Vec<Expr>,
+    pub values: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SetIndex {
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Match {
     pub keyword: Token,
+    pub value: Box<Expr>,
+    pub arms: Vec<MatchArm>,
 }
 
 impl Binary {
@@ -117,13 +182,17 @@ impl Unary {
 
 impl Variable {
     pub fn new(name: Token) -> Expr {
-        Expr::Variable(Variable { name })
+        Expr::Variable(Variable {
+            id: next_id(),
+            name,
+        })
     }
 }
 
 impl Assign {
     pub fn new(name: Token, value: Expr) -> Expr {
         Expr::Assign(Assign {
+            id: next_id(),
             name,
             value: Box::new(value),
         })
@@ -141,20 +210,22 @@ impl Logical {
 }
 
 impl Call {
-    pub fn new(callee: Expr, paren: Token, arguments: Vec<Expr>) -> Expr {
+    pub fn new(callee: Expr, paren: Token, arguments: Vec<Expr>, names: Vec<Token>) -> Expr {
         Expr::Call(Call {
             callee: Box::new(callee),
             paren,
             arguments,
+            names,
         })
     }
 }
 
 impl Get {
-    pub fn new(object: Expr, name: Token) -> Expr {
+    pub fn new(object: Expr, name: Token, optional: bool) -> Expr {
         Expr::Get(Get {
             object: Box::new(object),
             name,
+            optional,
         })
     }
 }
@@ -172,7 +243,66 @@ impl Set {
 impl This {
     pub fn new(keyword: Token) -> Expr {
         Expr::This(This {
-            keyword
+            id: next_id(),
+            keyword,
+        })
+    }
+}
+
+impl Conditional {
+    pub fn new(condition: Expr, question: Token, then_branch: Expr, else_branch: Expr) -> Expr {
+        Expr::Conditional(Conditional {
+            condition: Box::new(condition),
+            question,
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        })
+    }
+}
+
+impl List {
+    pub fn new(bracket: Token, elements: Vec<Expr>) -> Expr {
+        Expr::List(List { bracket, elements })
+    }
+}
+
+impl Map {
+    pub fn new(brace: Token, keys: Vec<Expr>, values: Vec<Expr>) -> Expr {
+        Expr::Map(Map {
+            brace,
+            keys,
+            values,
+        })
+    }
+}
+
+impl Index {
+    pub fn new(object: Expr, bracket: Token, index: Expr) -> Expr {
+        Expr::Index(Index {
+            object: Box::new(object),
+            bracket,
+            index: Box::new(index),
+        })
+    }
+}
+
+impl SetIndex {
+    pub fn new(object: Expr, bracket: Token, index: Expr, value: Expr) -> Expr {
+        Expr::SetIndex(SetIndex {
+            object: Box::new(object),
+            bracket,
+            index: Box::new(index),
+            value: Box::new(value),
+        })
+    }
+}
+
+impl Match {
+    pub fn new(keyword: Token, value: Expr, arms: Vec<MatchArm>) -> Expr {
+        Expr::Match(Match {
+            keyword,
+            value: Box::new(value),
+            arms,
         })
     }
 }
@@ -191,6 +321,12 @@ impl Expr {
             Expr::Get(e) => visitor.visit_get_expr(e),
             Expr::Set(e) => visitor.visit_set_expr(e),
             Expr::This(e) => visitor.visit_this_expr(e),
+            Expr::Conditional(e) => visitor.visit_conditional_expr(e),
+            Expr::List(e) => visitor.visit_list_expr(e),
+            Expr::Map(e) => visitor.visit_map_expr(e),
+            Expr::Index(e) => visitor.visit_index_expr(e),
+            Expr::SetIndex(e) => visitor.visit_set_index_expr(e),
+            Expr::Match(e) => visitor.visit_match_expr(e),
         }
     }
 }
@@ -198,7 +334,7 @@ impl Expr {
 pub trait Visitor<T> {
     fn visit_binary_expr(&mut self, expr: &Binary) -> T;
     fn visit_grouping_expr(&mut self, expr: &Grouping) -> T;
-    fn visit_literal_expr(&self, expr: &Literal) -> T;
+    fn visit_literal_expr(&mut self, expr: &Literal) -> T;
     fn visit_unary_expr(&mut self, expr: &Unary) -> T;
     fn visit_variable_expr(&mut self, expr: &Variable) -> T;
     fn visit_assign_expr(&mut self, expr: &Assign) -> T;
@@ -207,4 +343,10 @@ pub trait Visitor<T> {
     fn visit_get_expr(&mut self, expr: &Get) -> T;
     fn visit_set_expr(&mut self, expr: &Set) -> T;
     fn visit_this_expr(&mut self, expr: &This) -> T;
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> T;
+    fn visit_list_expr(&mut self, expr: &List) -> T;
+    fn visit_map_expr(&mut self, expr: &Map) -> T;
+    fn visit_index_expr(&mut self, expr: &Index) -> T;
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> T;
+    fn visit_match_expr(&mut self, expr: &Match) -> T;
 }
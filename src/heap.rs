@@ -0,0 +1,100 @@
+use crate::object::{Obj, Object};
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+/// Names of the `Obj` kinds, indexed by `Obj::kind`.
+pub const KINDS: [&str; 17] = [
+    "string", "number", "integer", "bool", "nil", "function", "class", "instance", "native", "module",
+    "list", "map", "range", "enum", "variant", "generator", "trait",
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KindStats {
+    pub objects: usize,
+    pub bytes: usize,
+}
+
+/// Live heap objects and their estimated size, per `Obj` kind.
+///
+/// Sizes count the object itself plus the text of strings, the slots of
+/// lists, map entries and instance fields; memory held by function
+/// declarations is not included. Each interpreter keeps its own, counting
+/// the objects allocated while it runs.
+#[derive(Debug, Clone, Default)]
+pub struct HeapStats {
+    pub kinds: [KindStats; KINDS.len()],
+}
+
+impl HeapStats {
+    pub fn objects(&self) -> usize {
+        self.kinds.iter().map(|kind| kind.objects).sum()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.kinds.iter().map(|kind| kind.bytes).sum()
+    }
+}
+
+/// Accounting shared by the objects one interpreter allocates.
+pub type Stats = Rc<RefCell<HeapStats>>;
+
+thread_local!(static CHARGED: RefCell<Option<Stats>> = const { RefCell::new(None) });
+
+/// Charges objects allocated on this thread to `stats` until the returned
+/// guard is dropped. Objects allocated outside any charge are not counted.
+pub fn charge(stats: Stats) -> Charge {
+    Charge(CHARGED.with(|charged| charged.replace(Some(stats))))
+}
+
+pub struct Charge(Option<Stats>);
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        CHARGED.with(|charged| *charged.borrow_mut() = self.0.take());
+    }
+}
+
+/// The estimated size of `obj`, as counted in `HeapStats`.
+pub fn size_of(obj: &Obj) -> usize {
+    let extra = match obj {
+        Obj::STRING(s) => s.capacity(),
+        Obj::List(items) => items.capacity() * mem::size_of::<Object>(),
+        Obj::Map(map) => map.size(),
+        Obj::Instance(instance) => instance.size(),
+        _ => 0,
+    };
+    mem::size_of::<Obj>() + extra
+}
+
+/// Counts `obj` against the current charge, which is returned so the object
+/// can be discounted from the same stats when it is resized or freed.
+pub fn allocated(obj: &Obj) -> Option<Stats> {
+    let stats = CHARGED.with(|charged| charged.borrow().clone())?;
+    {
+        let kind = &mut stats.borrow_mut().kinds[obj.kind()];
+        kind.objects += 1;
+        kind.bytes += size_of(obj);
+    }
+    Some(stats)
+}
+
+/// Records that `obj`, which measured `before` bytes, grew or shrank in place.
+pub fn resized(stats: &Stats, obj: &Obj, before: usize) {
+    let kind = &mut stats.borrow_mut().kinds[obj.kind()];
+    kind.bytes = (kind.bytes + size_of(obj)).saturating_sub(before);
+}
+
+pub fn freed(stats: &Stats, obj: &Obj) {
+    let kind = &mut stats.borrow_mut().kinds[obj.kind()];
+    kind.objects = kind.objects.saturating_sub(1);
+    kind.bytes = kind.bytes.saturating_sub(size_of(obj));
+}
+
+/// Runs a full collection and returns how many of the objects counted in
+/// `stats` it freed.
+pub fn collect(stats: &Stats) -> usize {
+    let before = stats.borrow().objects();
+    gc::force_collect();
+    before.saturating_sub(stats.borrow().objects())
+}
@@ -1,20 +1,50 @@
 use crate::environment::Environment;
+use crate::error;
 use crate::expr;
-use crate::expr::{Assign, Binary, Call, Expr, Get, Grouping, Literal, Logical, Unary, Variable, Set, This};
+use crate::expr::{Assign, Binary, Call, Conditional, Expr, Get, Grouping, Index, List, Literal, Logical, Map, Match, Unary, Variable, Set, SetIndex, This};
+use crate::heap::{self, HeapStats};
 use crate::lox_class::LoxClass;
-use crate::lox_function::{Callable, LoxFunction};
+use crate::lox_enum::{EnumVariant, LoxEnum};
+use crate::lox_function::{bind, Callable, LoxFunction};
+use crate::lox_generator::{yields, Frame, Phase};
+use crate::lox_instance::LoxInstance;
+use crate::lox_map::LoxMap;
+use crate::lox_module::LoxModule;
+use crate::lox_trait::LoxTrait;
+use crate::native;
 use crate::object::{Object, Obj};
+use crate::parser::Parser;
+use crate::pattern::Pattern;
+use crate::resolver::Resolver;
+use crate::scanner::Scanner;
 use crate::stmt;
-use crate::stmt::{Block, Class, Expression, Function, If, Print, Return, Stmt, Var, While};
+use crate::stmt::{Block, Break, Class, Continue, Enum, Expression, For, ForIn, Function, If, Import, Print, Return, Stmt, Throw, Trait, Try, Var, While, Yield};
 use crate::token::Token;
-use crate::tokentype::{Literals, TokenType};
+use gc_derive::{Finalize, Trace};
+use crate::tokentype::{FunctionKind, Literals, TokenType};
+use std::cmp;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::iter;
+use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct RuntimeError {
-    token: Token,
-    message: String,
+    pub token: Token,
+    pub message: String,
+    pub backtrace: Vec<TraceFrame>,
+    /// The value given to `throw`; `None` for errors raised by the
+    /// interpreter itself.
+    pub value: Option<Object>,
 }
 
 impl RuntimeError {
@@ -22,8 +52,192 @@ impl RuntimeError {
         RuntimeError {
             token: token.clone(),
             message: String::from(message),
+            backtrace: Vec::new(),
+            value: None,
         }
     }
+
+    /// An error raised by `throw`. If it is never caught, the value's
+    /// `message` field is reported when it has one, otherwise the value.
+    pub fn thrown(token: &Token, value: Object) -> RuntimeError {
+        let message = match value.borrow().deref() {
+            Obj::Instance(instance) => instance.field("message").map(|m| m.to_string()),
+            _ => None,
+        };
+        RuntimeError {
+            token: token.clone(),
+            message: message.unwrap_or_else(|| value.to_string()),
+            backtrace: Vec::new(),
+            value: Some(value),
+        }
+    }
+
+    /// What a `catch` clause binds: the thrown value, or an `Error` object
+    /// with `message` and `line` fields for the interpreter's own errors.
+    pub fn to_object(&self) -> Object {
+        if let Some(value) = &self.value {
+            return value.clone();
+        }
+        let class = LoxClass::new("Error".to_string(), HashMap::new());
+        let mut instance = LoxInstance::new(class);
+        instance.set_field("message".to_string(), Object::STRING(self.message.clone()));
+        instance.set_field("line".to_string(), Object::INTEGER(self.token.line as i64));
+        Object::Instance(instance)
+    }
+}
+
+/// One line of a runtime error's backtrace: the line being executed in a
+/// function, or in the top-level script when `function` is `None`.
+#[derive(Debug, Clone)]
+pub struct TraceFrame {
+    pub line: u64,
+    pub function: Option<String>,
+}
+
+impl fmt::Display for TraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.function {
+            Some(name) => write!(f, "[line {}] in {}()", self.line, name),
+            None => write!(f, "[line {}] in script", self.line),
+        }
+    }
+}
+
+/// Checks that `index` is a whole number within a sequence of `len` items.
+fn list_index(index: &Object, len: usize, bracket: &Token) -> Result<usize, RuntimeException> {
+    match index.borrow().deref() {
+        Obj::INTEGER(n) => {
+            if *n >= 0 && (*n as usize) < len {
+                Ok(*n as usize)
+            } else {
+                Err(RuntimeException::error(bracket, "Index out of range."))
+            }
+        }
+        Obj::NUMBER(n) if n.fract() == 0.0 => {
+            if *n >= 0.0 && (*n as usize) < len {
+                Ok(*n as usize)
+            } else {
+                Err(RuntimeException::error(bracket, "Index out of range."))
+            }
+        }
+        _ => Err(RuntimeException::error(bracket, "Index must be a whole number.")),
+    }
+}
+
+/// Two numeric operands: integers if both are, otherwise both as floats.
+enum Numbers {
+    INTEGERS(i64, i64),
+    FLOATS(f64, f64),
+}
+
+fn numbers(left: &Obj, right: &Obj) -> Option<Numbers> {
+    match (left, right) {
+        (Obj::INTEGER(l), Obj::INTEGER(r)) => Some(Numbers::INTEGERS(*l, *r)),
+        (Obj::INTEGER(l), Obj::NUMBER(r)) => Some(Numbers::FLOATS(*l as f64, *r)),
+        (Obj::NUMBER(l), Obj::INTEGER(r)) => Some(Numbers::FLOATS(*l, *r as f64)),
+        (Obj::NUMBER(l), Obj::NUMBER(r)) => Some(Numbers::FLOATS(*l, *r)),
+        _ => None,
+    }
+}
+
+fn numbers_equal(numbers: Numbers) -> bool {
+    match numbers {
+        Numbers::INTEGERS(l, r) => l == r,
+        Numbers::FLOATS(l, r) => (l - r).abs() < f64::EPSILON,
+    }
+}
+
+/// The result of integer arithmetic, or an error if it overflowed.
+fn checked(result: Option<i64>, operator: &Token) -> RTResult {
+    result
+        .map(Object::INTEGER)
+        .ok_or_else(|| RuntimeException::error(operator, "Integer overflow."))
+}
+
+/// The method an instance on the left of a binary `operator` defines to
+/// overload it; `!=` negates `__eq`.
+fn operator_method(operator: TokenType) -> Option<&'static str> {
+    match operator {
+        TokenType::PLUS => Some("__add"),
+        TokenType::MINUS => Some("__sub"),
+        TokenType::STAR => Some("__mul"),
+        TokenType::SLASH => Some("__div"),
+        TokenType::LESS => Some("__lt"),
+        TokenType::LESS_EQUAL => Some("__le"),
+        TokenType::GREATER => Some("__gt"),
+        TokenType::GREATER_EQUAL => Some("__ge"),
+        TokenType::EQUAL_EQUAL | TokenType::BANG_EQUAL => Some("__eq"),
+        _ => None,
+    }
+}
+
+/// The method an instance on the right of a binary `operator` defines, used
+/// when the left operand has none: `__radd` and the like for arithmetic,
+/// the mirrored comparison, and `__eq` again for equality.
+fn reflected_method(operator: TokenType) -> Option<&'static str> {
+    match operator {
+        TokenType::PLUS => Some("__radd"),
+        TokenType::MINUS => Some("__rsub"),
+        TokenType::STAR => Some("__rmul"),
+        TokenType::SLASH => Some("__rdiv"),
+        TokenType::LESS => Some("__gt"),
+        TokenType::LESS_EQUAL => Some("__ge"),
+        TokenType::GREATER => Some("__lt"),
+        TokenType::GREATER_EQUAL => Some("__le"),
+        TokenType::EQUAL_EQUAL | TokenType::BANG_EQUAL => Some("__eq"),
+        _ => None,
+    }
+}
+
+/// The method `name` of `object`'s class, if `object` is an instance.
+fn special_method(object: &Object, name: &str) -> Option<Object> {
+    match object.borrow().deref() {
+        Obj::Instance(instance) => instance.method(&name.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether a `match` literal equals `value`; unlike `==`, values of other
+/// types just don't match.
+fn literal_matches(literal: &Object, value: &Object) -> bool {
+    let (literal, value) = (literal.borrow(), value.borrow());
+    if let Some(numbers) = numbers(literal.deref(), value.deref()) {
+        return numbers_equal(numbers);
+    }
+    match (literal.deref(), value.deref()) {
+        (Obj::STRING(l), Obj::STRING(r)) => l == r,
+        (Obj::BOOL(l), Obj::BOOL(r)) => l == r,
+        (Obj::NIL(_), Obj::NIL(_)) => true,
+        _ => false,
+    }
+}
+
+/// Where a `for`-in loop is in its iterable: lists and ranges are indexed on
+/// each pass so a list can be changed while it is iterated.
+#[derive(Trace, Finalize, Debug)]
+pub enum Iteration {
+    SEQUENCE(Object, usize),
+    ITEMS(Vec<Object>, usize),
+    /// A user iterator and its `next` method.
+    ITERATOR(Object, Object),
+    /// Runs until the body finishes, so it may yield nil.
+    GENERATOR(Object),
+}
+
+/// What running one generator frame did.
+enum Step {
+    /// The frame is still running.
+    NEXT,
+    PUSH(Box<Frame>),
+    POP,
+    YIELD(Object),
+}
+
+/// An active call: the callee's name and the line it was called from.
+#[derive(Debug, Clone)]
+struct CallFrame {
+    function: String,
+    line: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -37,10 +251,33 @@ impl ReturnValue {
     }
 }
 
+/// Why the host's limits stopped a script. Unlike `ERROR`, an abort is never
+/// the script's fault, so it is reported separately.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AbortReason {
+    BUDGET,
+    TIMEOUT,
+    INTERRUPT,
+}
+
+impl fmt::Display for AbortReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AbortReason::BUDGET => write!(f, "Execution step budget exhausted."),
+            AbortReason::TIMEOUT => write!(f, "Execution timed out."),
+            AbortReason::INTERRUPT => write!(f, "Execution interrupted."),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RuntimeException {
     ERROR(RuntimeError),
     RETURN(ReturnValue),
+    ABORT(AbortReason),
+    /// `break` and `continue`, caught by the innermost loop.
+    BREAK,
+    CONTINUE,
 }
 
 impl RuntimeException {
@@ -58,40 +295,244 @@ static NUM_ERROR: &str = "Operands must be numbers.";
 static NUM_STR_ERROR: &str = "Operands must be two numbers or two strings.";
 static BOOL_ERROR: &str = "Operands must be bool.";
 
+/// Default limit on nested Lox calls before raising "Stack overflow.". A
+/// call can take tens of kilobytes of native stack in debug builds, so this
+/// stays within an 8MB thread stack; hosts running the interpreter on a
+/// larger stack can raise it with `set_max_call_depth`.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 200;
+
+/// Lets another thread stop a running `Interpreter`; the script aborts with
+/// `AbortReason::INTERRUPT` at its next statement or loop iteration. An
+/// interrupt sent while no script is running is dropped when the next
+/// `interpret` starts.
+#[derive(Debug, Clone)]
+pub struct InterruptHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+}
+
 pub struct Interpreter {
     pub globals: Environment,
+    out: Box<dyn Write>,
+    err: error::Sink,
+    input: Box<dyn BufRead>,
     environment: Environment,
-    locals: HashMap<usize, usize>,
+    locals: HashMap<usize, (usize, usize)>,
+    /// Slots the resolver gave local declarations, by name token id.
+    declarations: HashMap<usize, usize>,
+    frames: Vec<CallFrame>,
+    max_call_depth: usize,
+    steps: u64,
+    step_limit: Option<u64>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    interrupted: Arc<AtomicBool>,
+    memory_limit: Option<usize>,
+    /// Objects allocated while this interpreter runs.
+    heap: heap::Stats,
+    base_dir: PathBuf,
+    /// Modules already run, by canonical path.
+    modules: HashMap<PathBuf, Object>,
+    /// Modules being run, innermost last, for relative paths and cycles.
+    loading: Vec<PathBuf>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
-        let globals = Environment::new();
+        Interpreter::with_io(
+            Box::new(io::stdout()),
+            Box::new(io::stderr()),
+            Box::new(BufReader::new(io::stdin())),
+        )
+    }
+    /// Creates an interpreter that writes `print` output to `out`, reports
+    /// compile and runtime errors to `err` and reads `readLine()` input from
+    /// `input`.
+    pub fn with_io(
+        out: Box<dyn Write>,
+        err: Box<dyn Write>,
+        input: Box<dyn BufRead>,
+    ) -> Interpreter {
+        let heap = heap::Stats::default();
+        let _charge = heap::charge(heap.clone());
+        let mut globals = Environment::new();
+        native::define_globals(&mut globals);
         let env = globals.clone();
         Interpreter {
             globals,
+            out,
+            err: Rc::new(RefCell::new(err)),
+            input,
             environment: env,
             locals: HashMap::new(),
+            declarations: HashMap::new(),
+            frames: Vec::new(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            steps: 0,
+            step_limit: None,
+            timeout: None,
+            deadline: None,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            memory_limit: None,
+            heap,
+            base_dir: PathBuf::new(),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+        }
+    }
+    /// Sets how many calls may be nested before a "Stack overflow." runtime
+    /// error is raised. Each Lox call also uses native stack, so hosts raising
+    /// the limit should run the interpreter on a thread with a larger stack.
+    pub fn set_max_call_depth(&mut self, depth: usize) {
+        self.max_call_depth = depth;
+    }
+
+    /// Limits how many statements and loop iterations each `interpret` call
+    /// may execute; `None` removes the limit.
+    pub fn set_step_limit(&mut self, limit: Option<u64>) {
+        self.step_limit = limit;
+    }
+
+    /// Limits the wall-clock time of each `interpret` call; `None` removes
+    /// the limit.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Caps the estimated heap size (see `heap::HeapStats`) in bytes; `None`
+    /// removes the cap.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Live objects this interpreter allocated, per kind.
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.borrow().clone()
+    }
+
+    /// Runs a full collection and returns how many of this interpreter's
+    /// objects it freed.
+    pub fn collect_garbage(&self) -> usize {
+        heap::collect(&self.heap)
+    }
+
+    /// Makes the top-level script's imports relative to `path`'s directory
+    /// rather than the working directory.
+    pub fn set_script_path(&mut self, path: &Path) {
+        self.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    }
+
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        InterruptHandle {
+            flag: self.interrupted.clone(),
+        }
+    }
+
+    /// Scans, parses and resolves `source`, reporting errors and warnings to
+    /// the diagnostics stream. Fails if any error was reported.
+    pub fn compile(&mut self, source: &String) -> Result<Vec<Stmt>, ()> {
+        let _redirect = error::redirect(self.err.clone());
+        let _charge = heap::charge(self.heap.clone());
+        error::reset();
+        let mut scanner = Scanner::new(source);
+        let statements = Parser::new(scanner.scan_tokens()).parse().map_err(|_| ())?;
+        Resolver::new(self).resolves(&statements);
+        if error::had_error() {
+            return Err(());
         }
+        Ok(statements)
     }
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+
+    /// Runs `statements`, reporting any runtime error or abort to the
+    /// diagnostics stream before returning it.
+    pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<(), RuntimeException> {
+        let _charge = heap::charge(self.heap.clone());
+        self.steps = 0;
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.interrupted.store(false, Ordering::SeqCst);
         for statement in statements {
-            self.execute(&statement).unwrap();
+            if let Err(e) = self.execute(&statement) {
+                let e = self.with_backtrace(e);
+                let _ = match &e {
+                    RuntimeException::ERROR(error) => error::runtime_error(&mut *self.err.borrow_mut(), error),
+                    RuntimeException::ABORT(reason) => writeln!(self.err.borrow_mut(), "{}", reason),
+                    RuntimeException::RETURN(_) | RuntimeException::BREAK | RuntimeException::CONTINUE => Ok(()),
+                };
+                let _ = self.out.flush();
+                return Err(e);
+            }
+        }
+        let _ = self.out.flush();
+        Ok(())
+    }
+
+    /// Reads one line from the input stream, without its line terminator;
+    /// `None` at end of input.
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
         }
+        Ok(Some(line))
     }
     fn execute(&mut self, stmt: &Stmt) -> RTResult {
+        self.tick()?;
         stmt.accept(self)
     }
+
+    /// Counts one unit of work against the host's limits.
+    fn tick(&mut self) -> Result<(), RuntimeException> {
+        self.steps += 1;
+        if self.step_limit.is_some_and(|limit| self.steps > limit) {
+            return Err(RuntimeException::ABORT(AbortReason::BUDGET));
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(RuntimeException::ABORT(AbortReason::TIMEOUT));
+        }
+        if self.interrupted.swap(false, Ordering::SeqCst) {
+            return Err(RuntimeException::ABORT(AbortReason::INTERRUPT));
+        }
+        Ok(())
+    }
     fn evalute(&mut self, expr: &Expr) -> RTResult {
         expr.accept(self)
     }
+    /// Evaluates `expr` in `environment`, such as a default parameter value
+    /// in the scope of the call.
+    pub fn evaluate_in(&mut self, expr: &Expr, environment: Environment) -> RTResult {
+        let env = mem::replace(&mut self.environment, environment);
+        let result = self.evalute(expr);
+        self.environment = env;
+        result
+    }
     pub fn execute_block(&mut self, statements: &Vec<Stmt>, environment: Environment) -> RTResult {
         let env = self.environment.clone();
         self.environment = environment;
         // println!("current: {:?}\n", self.environment);
         // println!("statements: {:?}\n", statements);
         for statement in statements {
-            if let Err(e) = self.execute(&statement) {
+            if let Err(e) = self.execute(statement) {
                 self.environment = env;
                 return Err(e);
             }
@@ -101,15 +542,747 @@ impl Interpreter {
         Ok(Object::NIL())
     }
 
-    pub fn resolve(&mut self, token_id: usize, depth: usize) {
-        self.locals.insert(token_id, depth);
+    /// Runs a `for` loop inside the scope that holds its initializer.
+    fn execute_for(&mut self, stmt: &For) -> RTResult {
+        if let Some(initializer) = &stmt.initializer {
+            self.execute(initializer)?;
+        }
+        loop {
+            self.tick()?;
+            if let Some(condition) = &stmt.condition {
+                let b = self.evalute(condition)?.to_bool().map_err(|_| {
+                    RuntimeException::error(
+                        &stmt.token,
+                        "for statements condition type must be bool or nil",
+                    )
+                })?;
+                if !b {
+                    return Ok(Object::NIL());
+                }
+            }
+            if !self.loop_body(&stmt.body)? {
+                return Ok(Object::NIL());
+            }
+            if let Some(increment) = &stmt.increment {
+                self.evalute(increment)?;
+            }
+        }
+    }
+
+    /// An error raised inside a native function, reported at its call.
+    pub fn native_error(&self, message: &str) -> RuntimeException {
+        let frame = self.frames.last().expect("native_error called outside a native call");
+        let token = Token::new(TokenType::IDENTIFIER, frame.function.clone(), None, frame.line);
+        RuntimeException::error(&token, message)
+    }
+
+    /// Runs one pass of a loop body; `false` once it hits `break`.
+    fn loop_body(&mut self, body: &Stmt) -> Result<bool, RuntimeException> {
+        match self.execute(body) {
+            Ok(_) | Err(RuntimeException::CONTINUE) => Ok(true),
+            Err(RuntimeException::BREAK) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Starts iterating `iterable` for a `for`-in loop. User objects are
+    /// iterated through their `iter()` method, if they have one, and then
+    /// by calling `next()` until it returns nil. Generators run until their
+    /// body finishes.
+    fn iteration(&mut self, iterable: Object, token: &Token) -> Result<Iteration, RuntimeException> {
+        let items = match iterable.borrow().deref() {
+            Obj::List(_) | Obj::Range(_) => None,
+            Obj::STRING(s) => Some(s.chars().map(|c| Object::STRING(c.to_string())).collect::<Vec<_>>()),
+            Obj::Map(map) => Some(map.keys().to_vec()),
+            Obj::Instance(_) | Obj::Generator(_) => return self.user_iteration(iterable.clone(), token),
+            _ => {
+                return Err(RuntimeException::error(
+                    token,
+                    "Can only iterate over lists, maps, strings, ranges and iterators.",
+                ))
+            }
+        };
+        Ok(match items {
+            Some(items) => Iteration::ITEMS(items, 0),
+            None => Iteration::SEQUENCE(iterable, 0),
+        })
+    }
+
+    fn user_iteration(&mut self, object: Object, token: &Token) -> Result<Iteration, RuntimeException> {
+        let iter = match object.borrow().deref() {
+            Obj::Instance(i) => i.method(&"iter".to_string()),
+            _ => None,
+        };
+        let iterator = match iter {
+            Some(iter) => self.call_method(&iter, object, token, Vec::new())?,
+            None => object,
+        };
+        let next = match iterator.borrow().deref() {
+            Obj::Instance(i) => i.method(&"next".to_string()),
+            Obj::Generator(_) => return Ok(Iteration::GENERATOR(iterator.clone())),
+            _ => None,
+        };
+        match next {
+            Some(next) => Ok(Iteration::ITERATOR(iterator.clone(), next)),
+            None => Err(RuntimeException::error(token, "Iterator must have a 'next' method.")),
+        }
+    }
+
+    fn next(&mut self, iteration: &mut Iteration, token: &Token) -> Result<Option<Object>, RuntimeException> {
+        match iteration {
+            Iteration::SEQUENCE(sequence, i) => {
+                let item = match sequence.borrow().deref() {
+                    Obj::List(items) => items.get(*i).cloned(),
+                    Obj::Range(range) => range.get(*i),
+                    _ => unreachable!(),
+                };
+                *i += 1;
+                Ok(item)
+            }
+            Iteration::ITEMS(items, i) => {
+                *i += 1;
+                Ok(items.get(*i - 1).cloned())
+            }
+            Iteration::ITERATOR(iterator, next) => {
+                let item = self.call_method(next, iterator.clone(), token, Vec::new())?;
+                Ok(if item.is_nil() { None } else { Some(item) })
+            }
+            Iteration::GENERATOR(generator) => self.resume(generator),
+        }
+    }
+
+    /// Runs a generator to its next `yield`, returning the value; `None` once
+    /// its body has finished. A generator that raised an error is finished.
+    pub fn resume(&mut self, generator: &Object) -> Result<Option<Object>, RuntimeException> {
+        let started = match generator.borrow_mut().deref_mut() {
+            Obj::Generator(g) => g.start(),
+            _ => unreachable!(),
+        };
+        let (mut frames, globals) = match started {
+            Some(started) => started,
+            None => return Err(self.native_error("Generator is already running.")),
+        };
+        let globals = mem::replace(&mut self.globals, globals);
+        let env = self.environment.clone();
+        let result = self.run_frames(&mut frames);
+        self.environment = env;
+        self.globals = globals;
+        if !matches!(result, Ok(Some(_))) {
+            frames.clear();
+        }
+        if let Obj::Generator(g) = generator.borrow_mut().deref_mut() {
+            g.suspend(frames);
+        }
+        result
+    }
+
+    fn run_frames(&mut self, frames: &mut Vec<Frame>) -> Result<Option<Object>, RuntimeException> {
+        let mut unwinding = None;
+        loop {
+            if let Some(exception) = unwinding.take() {
+                match frames.pop() {
+                    Some(frame) => unwinding = self.unwind(frame, exception, frames),
+                    None => {
+                        return match exception {
+                            RuntimeException::RETURN(_) => Ok(None),
+                            e => Err(e),
+                        }
+                    }
+                }
+                continue;
+            }
+            let frame = match frames.last_mut() {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            match self.step(frame) {
+                Ok(Step::NEXT) => {}
+                Ok(Step::PUSH(frame)) => frames.push(*frame),
+                Ok(Step::POP) => {
+                    frames.pop();
+                }
+                Ok(Step::YIELD(value)) => return Ok(Some(value)),
+                Err(e) => unwinding = Some(e),
+            }
+        }
+    }
+
+    /// Advances the innermost frame of a generator.
+    fn step(&mut self, frame: &mut Frame) -> Result<Step, RuntimeException> {
+        match frame {
+            Frame::BLOCK { statements, next, env } => match statements.get(*next) {
+                Some(stmt) => {
+                    *next += 1;
+                    self.environment = env.clone();
+                    self.start(stmt)
+                }
+                None => Ok(Step::POP),
+            },
+            Frame::LOOP { stmt, env, started } => {
+                self.tick()?;
+                self.environment = env.clone();
+                let (token, kind, condition, body) = match stmt {
+                    Stmt::While(s) => (&s.token, "while", Some(&s.condition), &s.body),
+                    Stmt::For(s) => {
+                        if let (Some(increment), true) = (&s.increment, *started) {
+                            self.evalute(increment)?;
+                        }
+                        (&s.token, "for", s.condition.as_ref(), &s.body)
+                    }
+                    _ => unreachable!(),
+                };
+                *started = true;
+                if let Some(condition) = condition {
+                    let b = self.evalute(condition)?.to_bool().map_err(|_| {
+                        RuntimeException::error(token, &format!("{} statements condition type must be bool or nil", kind))
+                    })?;
+                    if !b {
+                        return Ok(Step::POP);
+                    }
+                }
+                // The loop frame stays below whatever the body pushes, and
+                // steps again once the body is done.
+                self.start(body)
+            }
+            Frame::FOR_IN { stmt, iteration, env } => {
+                self.environment = env.clone();
+                match self.next(iteration, &stmt.token)? {
+                    Some(item) => {
+                        self.tick()?;
+                        let mut environment = Environment::from_env(env.clone());
+                        environment.define_slot(self.slot(&stmt.name), stmt.name.lexeme.clone(), item);
+                        self.environment = environment;
+                        self.start(&stmt.body)
+                    }
+                    None => Ok(Step::POP),
+                }
+            }
+            Frame::TRY { stmt, env, phase, pending } => match phase {
+                Phase::START => {
+                    *phase = Phase::BODY;
+                    Ok(Step::PUSH(Box::new(Frame::BLOCK {
+                        statements: stmt.body.clone(),
+                        next: 0,
+                        env: Environment::from_env(env.clone()),
+                    })))
+                }
+                Phase::BODY | Phase::CATCH => match &stmt.finally_body {
+                    Some(finally_body) => {
+                        *phase = Phase::FINALLY;
+                        Ok(Step::PUSH(Box::new(Frame::BLOCK {
+                            statements: finally_body.clone(),
+                            next: 0,
+                            env: Environment::from_env(env.clone()),
+                        })))
+                    }
+                    None => Ok(Step::POP),
+                },
+                Phase::FINALLY => match pending.take() {
+                    Some(exception) => Err(exception),
+                    None => Ok(Step::POP),
+                },
+            },
+        }
+    }
+
+    /// Runs a statement of a generator body in the current environment.
+    /// Those that cannot yield run to completion; the rest get a frame.
+    fn start(&mut self, stmt: &Stmt) -> Result<Step, RuntimeException> {
+        if !yields(stmt) {
+            self.execute(stmt)?;
+            return Ok(Step::NEXT);
+        }
+        self.tick()?;
+        let env = self.environment.clone();
+        Ok(match stmt {
+            Stmt::Yield(s) => Step::YIELD(self.evalute(&s.value)?),
+            Stmt::Block(s) => Step::PUSH(Box::new(Frame::BLOCK {
+                statements: s.statements.clone(),
+                next: 0,
+                env: Environment::from_env(env),
+            })),
+            Stmt::If(s) => {
+                let condition = self.evalute(&s.condition)?.to_bool().map_err(|_| {
+                    RuntimeException::error(&s.token, "if statements condition type must be bool or nil")
+                })?;
+                let branch = if condition { Some(&s.then_branch) } else { s.else_branch.as_ref() };
+                match branch {
+                    Some(branch) => self.start(branch)?,
+                    None => Step::NEXT,
+                }
+            }
+            Stmt::While(_) => Step::PUSH(Box::new(Frame::LOOP {
+                stmt: stmt.clone(),
+                env,
+                started: false,
+            })),
+            Stmt::For(s) => {
+                let env = Environment::from_env(env);
+                self.environment = env.clone();
+                if let Some(initializer) = &s.initializer {
+                    self.execute(initializer)?;
+                }
+                Step::PUSH(Box::new(Frame::LOOP {
+                    stmt: stmt.clone(),
+                    env,
+                    started: false,
+                }))
+            }
+            Stmt::ForIn(s) => {
+                let iterable = self.evalute(&s.iterable)?;
+                Step::PUSH(Box::new(Frame::FOR_IN {
+                    stmt: s.clone(),
+                    iteration: self.iteration(iterable, &s.token)?,
+                    env,
+                }))
+            }
+            Stmt::Try(s) => Step::PUSH(Box::new(Frame::TRY {
+                stmt: s.clone(),
+                env,
+                phase: Phase::START,
+                pending: None,
+            })),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Pops `frame` while `exception` unwinds a generator, as its statement
+    /// would have handled it; returns the exception if it unwinds further.
+    fn unwind(&mut self, mut frame: Frame, exception: RuntimeException, frames: &mut Vec<Frame>) -> Option<RuntimeException> {
+        let handler = match &mut frame {
+            Frame::BLOCK { .. } => return Some(exception),
+            Frame::LOOP { .. } | Frame::FOR_IN { .. } => {
+                return match exception {
+                    RuntimeException::BREAK => None,
+                    RuntimeException::CONTINUE => {
+                        frames.push(frame);
+                        None
+                    }
+                    e => Some(e),
+                }
+            }
+            Frame::TRY { stmt, env, phase, pending } => match (&exception, &stmt.name) {
+                (RuntimeException::ERROR(error), Some(name)) if *phase == Phase::BODY => {
+                    let mut environment = Environment::from_env(env.clone());
+                    environment.define_slot(self.slot(name), name.lexeme.clone(), error.to_object());
+                    *phase = Phase::CATCH;
+                    Frame::BLOCK {
+                        statements: stmt.catch_body.clone(),
+                        next: 0,
+                        env: environment,
+                    }
+                }
+                // An abort stops the script outright; nothing more of it may run.
+                _ if *phase == Phase::FINALLY || matches!(exception, RuntimeException::ABORT(_)) => {
+                    return Some(exception)
+                }
+                _ => match &stmt.finally_body {
+                    Some(finally_body) => {
+                        let finally = Frame::BLOCK {
+                            statements: finally_body.clone(),
+                            next: 0,
+                            env: Environment::from_env(env.clone()),
+                        };
+                        *phase = Phase::FINALLY;
+                        *pending = Some(exception);
+                        finally
+                    }
+                    None => return Some(exception),
+                },
+            },
+        };
+        frames.push(frame);
+        frames.push(handler);
+        None
     }
 
-    fn lookup_variable(&self, name: &Token) -> RTResult {
-        let distance = self.locals.get(&name.id);
-        match distance {
-            Some(d) => self.environment.get_at(*d, &name.lexeme),
-            None => self.globals.get(&name),
+    /// Attaches the current call stack to a runtime error the first time it
+    /// unwinds through a call, while the frames it was raised in still exist.
+    fn with_backtrace(&self, exception: RuntimeException) -> RuntimeException {
+        match exception {
+            RuntimeException::ERROR(mut error) if error.backtrace.is_empty() => {
+                let mut line = error.token.line;
+                for frame in self.frames.iter().rev() {
+                    error.backtrace.push(TraceFrame {
+                        line,
+                        function: Some(frame.function.clone()),
+                    });
+                    line = frame.line;
+                }
+                error.backtrace.push(TraceFrame {
+                    line,
+                    function: None,
+                });
+                RuntimeException::ERROR(error)
+            }
+            _ => exception,
+        }
+    }
+
+    /// `arguments` has a slot per parameter up to the last one passed;
+    /// parameters left empty by named arguments take their defaults.
+    fn call<C: Callable>(&mut self, callee: &C, paren: &Token, arguments: Vec<Option<Object>>) -> RTResult {
+        let arity = callee.arity();
+        if !arity.accepts(arguments.len()) {
+            return Err(RuntimeException::error(
+                paren,
+                format!(
+                    "Expected {} arguments but got {}.",
+                    arity,
+                    arguments.len(),
+                )
+                .as_str(),
+            ));
+        }
+        if let Some(missing) = (0..arity.min).find(|&i| arguments[i].is_none()) {
+            return Err(RuntimeException::error(
+                paren,
+                &format!("Missing argument '{}'.", callee.params()[missing]),
+            ));
+        }
+        if self.frames.len() >= self.max_call_depth {
+            return Err(RuntimeException::error(paren, "Stack overflow."));
+        }
+        self.frames.push(CallFrame {
+            function: callee.name(),
+            line: paren.line,
+        });
+        let result = callee.call_partial(self, arguments).map_err(|e| self.with_backtrace(e));
+        self.frames.pop();
+        result
+    }
+
+    /// Evaluates a chain of property accesses and calls; `None` once a `?.`
+    /// meets nil, which skips the rest of the chain.
+    fn chain(&mut self, expr: &Expr) -> Result<Option<Object>, RuntimeException> {
+        match expr {
+            Expr::Get(get) => self.get_chain(get),
+            Expr::Call(call) => self.call_chain(call),
+            Expr::Index(index) => self.index_chain(index),
+            _ => self.evalute(expr).map(Some),
+        }
+    }
+    fn index_chain(&mut self, expr: &Index) -> Result<Option<Object>, RuntimeException> {
+        match self.chain(&expr.object)? {
+            Some(object) => {
+                let index = self.evalute(&expr.index)?;
+                self.index(expr, object, index).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+    fn index(&mut self, expr: &Index, object: Object, index: Object) -> RTResult {
+        if let Some(method) = special_method(&object, "__index") {
+            return self.call_method(&method, object, &expr.bracket, vec![index]);
+        }
+        match object.borrow().deref() {
+            Obj::List(items) => Ok(items[list_index(&index, items.len(), &expr.bracket)?].clone()),
+            Obj::STRING(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                let i = list_index(&index, chars.len(), &expr.bracket)?;
+                Ok(Object::STRING(chars[i].to_string()))
+            }
+            Obj::Map(map) => map.get(&index, &expr.bracket),
+            _ => Err(RuntimeException::error(&expr.bracket, "Only lists, maps and strings can be indexed.")),
+        }
+    }
+    /// Matches `value` against `pattern`, pushing the names it binds in the
+    /// order the resolver declared them.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Object,
+        bindings: &mut Vec<(Option<usize>, String, Object)>,
+    ) -> Result<bool, RuntimeException> {
+        match pattern {
+            Pattern::WILDCARD(_) => Ok(true),
+            Pattern::LITERAL(literal) => Ok(literal_matches(&self.evalute(literal)?, value)),
+            Pattern::BINDING(name) => {
+                bindings.push((self.slot(name), name.lexeme.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::ALTERNATIVE(alternatives) => {
+                for alternative in alternatives {
+                    if self.match_pattern(alternative, value, bindings)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Pattern::INSTANCE(class, fields) => {
+                let class_o = self.evalute(class)?;
+                let matches = match (class_o.borrow().deref(), value.borrow().deref()) {
+                    (Obj::Class(c), Obj::Instance(i)) => i.is_instance_of(c),
+                    (Obj::Class(_), _) => false,
+                    _ => {
+                        let name = match class {
+                            Expr::Variable(v) => &v.name,
+                            _ => unreachable!(),
+                        };
+                        return Err(RuntimeException::error(name, "Pattern type must be a class."));
+                    }
+                };
+                if !matches {
+                    return Ok(false);
+                }
+                for (name, field) in fields {
+                    let field_value = match value.borrow().deref() {
+                        Obj::Instance(i) => i.field(&name.lexeme),
+                        _ => unreachable!(),
+                    };
+                    match field_value {
+                        Some(field_value) if self.match_pattern(field, &field_value, bindings)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+    fn get_chain(&mut self, expr: &Get) -> Result<Option<Object>, RuntimeException> {
+        match self.chain(&expr.object)? {
+            Some(object) if !(expr.optional && object.is_nil()) => self.get(expr, object).map(Some),
+            _ => Ok(None),
+        }
+    }
+    fn call_chain(&mut self, expr: &Call) -> Result<Option<Object>, RuntimeException> {
+        match self.chain(&expr.callee)? {
+            Some(callee) => self.call_value(expr, callee).map(Some),
+            None => Ok(None),
+        }
+    }
+    fn call_value(&mut self, expr: &Call, callee_o: Object) -> RTResult {
+        let callee_b = callee_o.borrow();
+        let callee = callee_b.deref();
+        let mut arguments: Vec<Object> = Vec::new();
+        for argument in expr.arguments.iter() {
+            arguments.push(self.evalute(argument)?);
+        }
+        self.check_memory(&expr.paren)?;
+        match callee {
+            Obj::Function(func) => self.call_named(func, expr, arguments),
+            Obj::Class(class) => self.call_named(class, expr, arguments),
+            Obj::Native(native) => self.call_named(native, expr, arguments),
+            _ => Err(RuntimeException::error(
+                &expr.paren,
+                "Can only call functions and classes.",
+            )),
+        }
+    }
+    fn get(&mut self, expr: &Get, object: Object) -> RTResult {
+        let o_b = object.borrow();
+        if let Obj::Instance(i) = o_b.deref() {
+            if let Some(getter) = i.getter(&expr.name.lexeme) {
+                drop(o_b);
+                return self.call_method(&getter, object.clone(), &expr.name, Vec::new());
+            }
+            Ok(i.get(expr, object.clone())?)
+        } else if let Obj::Class(c) = o_b.deref() {
+            match c.find_static(&expr.name.lexeme) {
+                Some(method) => Ok(bind(&method, object.clone())),
+                None => Err(RuntimeException::error(
+                    &expr.name,
+                    &format!("Undefined static method '{}'.", &expr.name.lexeme),
+                )),
+            }
+        } else if let Obj::Module(m) = o_b.deref() {
+            m.get(&expr.name)
+        } else if let Obj::Enum(e) = o_b.deref() {
+            if expr.name.lexeme == "values" {
+                Ok(Object::Native(native::enum_values().bind(object.clone())))
+            } else {
+                e.get(&expr.name)
+            }
+        } else if let Obj::EnumVariant(variant) = o_b.deref() {
+            variant.get(&expr.name)
+        } else if let (Obj::Generator(_), "next") = (o_b.deref(), expr.name.lexeme.as_str()) {
+            Ok(Object::Native(native::generator_next().bind(object.clone())))
+        } else if let (Obj::Generator(_), "done") = (o_b.deref(), expr.name.lexeme.as_str()) {
+            Ok(Object::Native(native::generator_done().bind(object.clone())))
+        } else {
+            Err(RuntimeException::error(
+                    &expr.name,
+                    "Only instances have properties."
+                    ))
+        }
+    }
+
+    /// Moves the trailing named arguments of a call into their parameters' slots.
+    fn call_named<C: Callable>(&mut self, callee: &C, expr: &Call, mut arguments: Vec<Object>) -> RTResult {
+        let named = arguments.split_off(arguments.len() - expr.names.len());
+        let mut slots: Vec<Option<Object>> = arguments.into_iter().map(Some).collect();
+        if !named.is_empty() {
+            let params = callee.params();
+            for (name, value) in expr.names.iter().zip(named) {
+                let index = match params.iter().position(|p| *p == name.lexeme) {
+                    Some(index) => index,
+                    None => {
+                        return Err(RuntimeException::error(
+                            name,
+                            &format!("Unknown parameter '{}'.", name.lexeme),
+                        ))
+                    }
+                };
+                if slots.len() <= index {
+                    slots.resize(index + 1, None);
+                }
+                if slots[index].is_some() {
+                    return Err(RuntimeException::error(
+                        name,
+                        &format!("Argument '{}' passed twice.", name.lexeme),
+                    ));
+                }
+                slots[index] = Some(value);
+            }
+        }
+        self.call(callee, &expr.paren, slots)
+    }
+
+    /// Calls a method, getter or setter on `this`.
+    fn call_method(&mut self, accessor: &Object, this: Object, name: &Token, arguments: Vec<Object>) -> RTResult {
+        let bound = bind(accessor, this);
+        let b = bound.borrow();
+        let arguments = arguments.into_iter().map(Some).collect();
+        match b.deref() {
+            Obj::Function(f) => self.call(f, name, arguments),
+            Obj::Native(f) => self.call(f, name, arguments),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Formats `object` for `print`, using `__str` for instances that define
+    /// it, including those inside lists and maps.
+    fn stringify(&mut self, object: &Object, token: &Token) -> Result<String, RuntimeException> {
+        self.stringify_within(object, token, &mut Vec::new())
+    }
+
+    /// `enclosing` holds the lists and maps being printed around `object`;
+    /// one that contains itself is elided the second time.
+    fn stringify_within(&mut self, object: &Object, token: &Token, enclosing: &mut Vec<Object>) -> Result<String, RuntimeException> {
+        if let Some(method) = special_method(object, "__str") {
+            let result = self.call_method(&method, object.clone(), token, Vec::new())?;
+            return match result.borrow().deref() {
+                Obj::STRING(s) => Ok(s.clone()),
+                _ => Err(RuntimeException::error(token, "'__str' must return a string.")),
+            };
+        }
+        let (open, close, keys, values) = match object.borrow().deref() {
+            Obj::List(items) => ("[", "]", Vec::new(), items.clone()),
+            Obj::Map(map) => ("{", "}", map.keys().to_vec(), map.values().to_vec()),
+            _ => return Ok(object.to_string()),
+        };
+        if enclosing.iter().any(|o| o.is_same(object)) {
+            return Ok(object.elided().unwrap_or_default().to_string());
+        }
+        enclosing.push(object.clone());
+        let mut out = open.to_string();
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            if let Some(key) = keys.get(i) {
+                out.push_str(&self.stringify_within(key, token, enclosing)?);
+                out.push_str(": ");
+            }
+            out.push_str(&self.stringify_within(value, token, enclosing)?);
+        }
+        enclosing.pop();
+        out.push_str(close);
+        Ok(out)
+    }
+
+    /// Raises "Out of memory." if the heap is still over the memory limit
+    /// after a collection. Checked where scripts can grow the heap without
+    /// bound: calls, binary operators and property sets.
+    fn check_memory(&mut self, token: &Token) -> Result<(), RuntimeException> {
+        if let Some(limit) = self.memory_limit {
+            let bytes = || self.heap.borrow().bytes();
+            if bytes() > limit && {
+                heap::collect(&self.heap);
+                bytes() > limit
+            } {
+                return Err(RuntimeException::error(token, "Out of memory."));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the module at `path`, relative to the importing file, in its own
+    /// globals the first time it is imported; later imports share the result.
+    fn import(&mut self, path: &Token) -> RTResult {
+        let relative = match &path.literal {
+            Some(Literals::STRING(s)) => s.clone(),
+            _ => unreachable!(),
+        };
+        let base = match self.loading.last() {
+            Some(importer) => importer.parent().unwrap().to_path_buf(),
+            None => self.base_dir.clone(),
+        };
+        let cannot_open = || {
+            RuntimeException::error(path, &format!("Cannot open module '{}'.", relative))
+        };
+        let canonical = base.join(&relative).canonicalize().map_err(|_| cannot_open())?;
+        if let Some(module) = self.modules.get(&canonical) {
+            return Ok(module.clone());
+        }
+        if let Some(start) = self.loading.iter().position(|p| *p == canonical) {
+            let cycle: Vec<String> = self.loading[start..]
+                .iter()
+                .chain(iter::once(&canonical))
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            return Err(RuntimeException::error(
+                path,
+                &format!("Import cycle: {}.", cycle.join(" -> ")),
+            ));
+        }
+
+        let source = fs::read_to_string(&canonical).map_err(|_| cannot_open())?;
+        let compile_error = RuntimeException::error(
+            path,
+            &format!("Could not compile module '{}'.", relative),
+        );
+        let statements = self.compile(&source).map_err(|_| compile_error)?;
+
+        let mut globals = Environment::new();
+        native::define_globals(&mut globals);
+        let outer_globals = mem::replace(&mut self.globals, globals.clone());
+        let outer_environment = mem::replace(&mut self.environment, globals.clone());
+        self.loading.push(canonical.clone());
+        let result = statements.iter().try_for_each(|statement| self.execute(statement).map(|_| ()));
+        self.loading.pop();
+        self.globals = outer_globals;
+        self.environment = outer_environment;
+        result?;
+
+        let name = canonical.file_stem().unwrap().to_string_lossy().into_owned();
+        let module = Object::Module(LoxModule::new(name, globals));
+        self.modules.insert(canonical, module.clone());
+        Ok(module)
+    }
+
+    pub fn resolve(&mut self, expr_id: usize, depth: usize, slot: usize) {
+        self.locals.insert(expr_id, (depth, slot));
+    }
+
+    pub fn resolve_declaration(&mut self, name: &Token, slot: usize) {
+        self.declarations.insert(name.id, slot);
+    }
+
+    /// The slot the resolver gave the local `name`, checked against the
+    /// order locals are defined in by debug builds.
+    pub(crate) fn slot(&self, name: &Token) -> Option<usize> {
+        if cfg!(debug_assertions) {
+            self.declarations.get(&name.id).copied()
+        } else {
+            None
+        }
+    }
+
+    fn lookup_variable(&self, expr_id: usize, name: &Token) -> RTResult {
+        match self.locals.get(&expr_id) {
+            Some(&(depth, slot)) => self.environment.get_at(depth, slot),
+            None => self.globals.get(name),
         }
     }
 }
@@ -118,62 +1291,86 @@ impl Interpreter {
 impl expr::Visitor<RTResult> for Interpreter {
     fn visit_binary_expr(&mut self, expr: &Binary) -> RTResult {
         let left_o = self.evalute(&expr.left)?;
+        let right_o = self.evalute(&expr.right)?;
+        let operator = expr.operator.token_type;
+        let overload = match operator_method(operator).and_then(|name| special_method(&left_o, name)) {
+            Some(method) => Some((method, left_o.clone(), right_o.clone())),
+            None => reflected_method(operator)
+                .and_then(|name| special_method(&right_o, name))
+                .map(|method| (method, right_o.clone(), left_o.clone())),
+        };
+        if let Some((method, this, other)) = overload {
+            let result = self.call_method(&method, this, &expr.operator, vec![other])?;
+            if operator != TokenType::BANG_EQUAL {
+                return Ok(result);
+            }
+            let b = result
+                .to_bool()
+                .map_err(|_| RuntimeException::error(&expr.operator, "'__eq' must return a bool."))?;
+            return Ok(Object::BOOL(!b));
+        }
         let left_b = left_o.borrow();
         let left = left_b.deref();
-        let right_o = self.evalute(&expr.right)?;
         let right_b = right_o.borrow();
         let right = right_b.deref();
+        self.check_memory(&expr.operator)?;
 
         match expr.operator.token_type {
-            TokenType::PLUS => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::NUMBER(l + r)),
-                (Obj::STRING(l), Obj::STRING(r)) => Ok(Object::STRING(l.to_owned() + r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_STR_ERROR)),
-            },
-            TokenType::MINUS => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::NUMBER(l - r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
-            },
-            TokenType::SLASH => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::NUMBER(l / r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
-            },
-            TokenType::STAR => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::NUMBER(l * r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
+            TokenType::PLUS => match numbers(left, right) {
+                Some(Numbers::INTEGERS(l, r)) => checked(l.checked_add(r), &expr.operator),
+                Some(Numbers::FLOATS(l, r)) => Ok(Object::NUMBER(l + r)),
+                None => match (left, right) {
+                    (Obj::STRING(l), Obj::STRING(r)) => Ok(Object::STRING(l.to_owned() + r)),
+                    _ => Err(RuntimeException::error(&expr.operator, NUM_STR_ERROR)),
+                },
             },
-            TokenType::GREATER => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::BOOL(l > r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
+            TokenType::MINUS => match numbers(left, right) {
+                Some(Numbers::INTEGERS(l, r)) => checked(l.checked_sub(r), &expr.operator),
+                Some(Numbers::FLOATS(l, r)) => Ok(Object::NUMBER(l - r)),
+                None => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
             },
-            TokenType::GREATER_EQUAL => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::BOOL(l >= r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
-            },
-            TokenType::LESS => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::BOOL(l < r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
+            // Division is always exact, so integers divide as floats.
+            TokenType::SLASH => match numbers(left, right) {
+                Some(Numbers::INTEGERS(l, r)) => Ok(Object::NUMBER(l as f64 / r as f64)),
+                Some(Numbers::FLOATS(l, r)) => Ok(Object::NUMBER(l / r)),
+                None => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
             },
-            TokenType::LESS_EQUAL => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => Ok(Object::BOOL(l <= r)),
-                _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
+            TokenType::STAR => match numbers(left, right) {
+                Some(Numbers::INTEGERS(l, r)) => checked(l.checked_mul(r), &expr.operator),
+                Some(Numbers::FLOATS(l, r)) => Ok(Object::NUMBER(l * r)),
+                None => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
             },
-            TokenType::BANG_EQUAL => match (left, right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => {
-                    // Ok(Obj::BOOL(l != r))
-                    Ok(Object::BOOL((l - r).abs() >= std::f64::EPSILON))
+            TokenType::GREATER | TokenType::GREATER_EQUAL | TokenType::LESS | TokenType::LESS_EQUAL => {
+                let ordering = match numbers(left, right) {
+                    Some(Numbers::INTEGERS(l, r)) => Some(l.cmp(&r)),
+                    Some(Numbers::FLOATS(l, r)) => l.partial_cmp(&r),
+                    None => return Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
+                };
+                let result = match expr.operator.token_type {
+                    TokenType::GREATER => ordering == Some(cmp::Ordering::Greater),
+                    TokenType::GREATER_EQUAL => matches!(ordering, Some(cmp::Ordering::Greater | cmp::Ordering::Equal)),
+                    TokenType::LESS => ordering == Some(cmp::Ordering::Less),
+                    _ => matches!(ordering, Some(cmp::Ordering::Less | cmp::Ordering::Equal)),
+                };
+                Ok(Object::BOOL(result))
+            }
+            TokenType::BANG_EQUAL => match (numbers(left, right), left, right) {
+                (Some(numbers), _, _) => Ok(Object::BOOL(!numbers_equal(numbers))),
+                (None, Obj::NIL(_), Obj::NIL(_)) => Ok(Object::BOOL(false)),
+                (None, Obj::NIL(_), _) => Ok(Object::BOOL(true)),
+                (None, Obj::EnumVariant(_), _) | (None, _, Obj::EnumVariant(_)) => {
+                    Ok(Object::BOOL(!left_o.is_same(&right_o)))
                 }
-                (Obj::NIL(_), Obj::NIL(_)) => Ok(Object::BOOL(false)),
-                (Obj::NIL(_), _) => Ok(Object::BOOL(true)),
                 _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
             },
-            TokenType::EQUAL_EQUAL => match (&left, &right) {
-                (Obj::NUMBER(l), Obj::NUMBER(r)) => {
-                    Ok(Object::BOOL((l - r).abs() < std::f64::EPSILON))
+            TokenType::EQUAL_EQUAL => match (numbers(left, right), left, right) {
+                (Some(numbers), _, _) => Ok(Object::BOOL(numbers_equal(numbers))),
+                (None, Obj::STRING(l), Obj::STRING(r)) => Ok(Object::BOOL(l == r)),
+                (None, Obj::NIL(_), Obj::NIL(_)) => Ok(Object::BOOL(true)),
+                (None, Obj::NIL(_), _) => Ok(Object::BOOL(false)),
+                (None, Obj::EnumVariant(_), _) | (None, _, Obj::EnumVariant(_)) => {
+                    Ok(Object::BOOL(left_o.is_same(&right_o)))
                 }
-                (Obj::STRING(l), Obj::STRING(r)) => Ok(Object::BOOL(l == r)),
-                (Obj::NIL(_), Obj::NIL(_)) => Ok(Object::BOOL(true)),
-                (Obj::NIL(_), _) => Ok(Object::BOOL(false)),
                 _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
             },
             _ => {
@@ -184,9 +1381,10 @@ impl expr::Visitor<RTResult> for Interpreter {
     fn visit_grouping_expr(&mut self, expr: &Grouping) -> RTResult {
         self.evalute(&expr.expression)
     }
-    fn visit_literal_expr(&self, expr: &Literal) -> RTResult {
+    fn visit_literal_expr(&mut self, expr: &Literal) -> RTResult {
         match expr.value.clone() {
             Literals::NUMBER(n) => Ok(Object::NUMBER(n)),
+            Literals::INTEGER(n) => Ok(Object::INTEGER(n)),
             Literals::STRING(s) => Ok(Object::STRING(s)),
             Literals::BOOL(s) => Ok(Object::BOOL(s)),
             Literals::NIL(_) => Ok(Object::NIL()),
@@ -195,10 +1393,16 @@ impl expr::Visitor<RTResult> for Interpreter {
 
     fn visit_unary_expr(&mut self, expr: &Unary) -> RTResult {
         let right = self.evalute(&expr.right)?;
+        if expr.operator.token_type == TokenType::MINUS {
+            if let Some(method) = special_method(&right, "__neg") {
+                return self.call_method(&method, right, &expr.operator, Vec::new());
+            }
+        }
 
         match expr.operator.token_type {
             TokenType::MINUS => match right.borrow().deref() {
                 Obj::NUMBER(n) => Ok(Object::NUMBER(-n)),
+                Obj::INTEGER(n) => checked(n.checked_neg(), &expr.operator),
                 _ => Err(RuntimeException::error(&expr.operator, NUM_ERROR)),
             },
             TokenType::BANG => {
@@ -214,13 +1418,12 @@ impl expr::Visitor<RTResult> for Interpreter {
     }
     fn visit_variable_expr(&mut self, expr: &Variable) -> RTResult {
         // self.environment.borrow().get(&expr.name)
-        self.lookup_variable(&expr.name)
+        self.lookup_variable(expr.id, &expr.name)
     }
     fn visit_assign_expr(&mut self, expr: &Assign) -> RTResult {
         let value = self.evalute(&expr.value)?;
-        let distance = self.locals.get(&expr.name.id);
-        match distance {
-            Some(d) => self.environment.assign_at(*d, &expr.name, value),
+        match self.locals.get(&expr.id) {
+            Some(&(depth, slot)) => self.environment.assign_at(depth, slot, value),
             None => self.globals.assign(&expr.name, value),
         }
         // self.environment
@@ -229,6 +1432,9 @@ impl expr::Visitor<RTResult> for Interpreter {
     }
     fn visit_logical_expr(&mut self, expr: &Logical) -> RTResult {
         let left = self.evalute(&expr.left)?;
+        if let TokenType::QUESTION_QUESTION = expr.operator.token_type {
+            return if left.is_nil() { self.evalute(&expr.right) } else { Ok(left) };
+        }
         let b = left
             .to_bool()
             .map_err(|_| RuntimeException::error(&expr.operator, ""))?;
@@ -248,78 +1454,112 @@ impl expr::Visitor<RTResult> for Interpreter {
         self.evalute(&expr.right)
     }
     fn visit_call_expr(&mut self, expr: &Call) -> RTResult {
-        let callee_o = self.evalute(&expr.callee)?;
-        let callee_b = callee_o.borrow();
-        let callee = callee_b.deref();
-        let mut arguments: Vec<Object> = Vec::new();
-        for argument in expr.arguments.iter() {
-            arguments.push(self.evalute(&argument)?);
+        Ok(self.call_chain(expr)?.unwrap_or_else(Object::NIL))
+    }
+    fn visit_get_expr(&mut self, expr: &Get) -> RTResult {
+        Ok(self.get_chain(expr)?.unwrap_or_else(Object::NIL))
+    }
+    fn visit_set_expr(&mut self, expr: &Set) -> RTResult {
+        let object = self.evalute(&expr.object)?;
+        let setter = match object.borrow().deref() {
+            Obj::Instance(i) => i.setter(&expr.name.lexeme),
+            _ => None,
+        };
+        let value = self.evalute(&expr.value)?;
+        if let Some(setter) = setter {
+            self.call_method(&setter, object, &expr.name, vec![value.clone()])?;
+            return Ok(value);
         }
-        match callee {
-            Obj::Function(func) => {
-                if arguments.len() != func.arity() {
-                    Err(RuntimeException::error(
-                        &expr.paren,
-                        format!(
-                            "Expected {} arguments but got {}.",
-                            func.arity(),
-                            arguments.len(),
-                        )
-                        .as_str(),
-                    ))
-                } else {
-                    func.call(self, arguments)
-                }
+        {
+            let mut o_b = object.borrow_mut();
+            let before = heap::size_of(&o_b);
+            if let Obj::Instance(ref mut i) = o_b.deref_mut() {
+                i.set(expr, value)?;
+            } else {
+                return Err(RuntimeException::error(
+                        &expr.name,
+                        "Only instances have properties."
+                        ));
             }
-            Obj::Class(class) => {
-                if arguments.len() != class.arity() {
-                    Err(RuntimeException::error(
-                        &expr.paren,
-                        format!(
-                            "Expected {} arguments but got {}.",
-                            class.arity(),
-                            arguments.len(),
-                        )
-                        .as_str(),
+            object.resized(&o_b, before);
+        }
+        self.check_memory(&expr.name)?;
+        Ok(Object::NIL())
+    }
+    fn visit_this_expr(&mut self, expr: &This) -> RTResult {
+        self.lookup_variable(expr.id, &expr.keyword)
+    }
+    fn visit_list_expr(&mut self, expr: &List) -> RTResult {
+        let mut elements = Vec::new();
+        for element in expr.elements.iter() {
+            elements.push(self.evalute(element)?);
+        }
+        self.check_memory(&expr.bracket)?;
+        Ok(Object::List(elements))
+    }
+    fn visit_map_expr(&mut self, expr: &Map) -> RTResult {
+        let mut map = LoxMap::new();
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            let key = self.evalute(key)?;
+            let value = self.evalute(value)?;
+            map.set(key, value, &expr.brace)?;
+        }
+        self.check_memory(&expr.brace)?;
+        Ok(Object::Map(map))
+    }
+    fn visit_index_expr(&mut self, expr: &Index) -> RTResult {
+        Ok(self.index_chain(expr)?.unwrap_or_else(Object::NIL))
+    }
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) -> RTResult {
+        let object = self.evalute(&expr.object)?;
+        let index = self.evalute(&expr.index)?;
+        let value = self.evalute(&expr.value)?;
+        {
+            let mut o_b = object.borrow_mut();
+            let before = heap::size_of(&o_b);
+            match o_b.deref_mut() {
+                Obj::List(items) => {
+                    let i = list_index(&index, items.len(), &expr.bracket)?;
+                    items[i] = value.clone();
+                }
+                Obj::Map(map) => map.set(index, value.clone(), &expr.bracket)?,
+                _ => {
+                    return Err(RuntimeException::error(
+                        &expr.bracket,
+                        "Only lists and maps support index assignment.",
                     ))
-                } else {
-                    class.call(self, arguments)
                 }
             }
-            _ => Err(RuntimeException::error(
-                &expr.paren,
-                "Can only call functions and classes.",
-            )),
+            object.resized(&o_b, before);
         }
+        self.check_memory(&expr.bracket)?;
+        Ok(value)
     }
-    fn visit_get_expr(&mut self, expr: &Get) -> RTResult {
-        let object = self.evalute(&expr.object)?;
-        let o_b = object.borrow();
-        if let Obj::Instance(i) = o_b.deref() {
-            Ok(i.get(&expr)?)
-        } else {
-            Err(RuntimeException::error(
-                    &expr.name,
-                    "Only instances have properties."
-                    ))
+    fn visit_match_expr(&mut self, expr: &Match) -> RTResult {
+        let value = self.evalute(&expr.value)?;
+        for arm in expr.arms.iter() {
+            let mut bindings = Vec::new();
+            if self.match_pattern(&arm.pattern, &value, &mut bindings)? {
+                let mut environment = Environment::from_env(self.environment.clone());
+                for (slot, name, binding) in bindings {
+                    environment.define_slot(slot, name, binding);
+                }
+                return self.evaluate_in(&arm.body, environment);
+            }
         }
+        Ok(Object::NIL())
     }
-    fn visit_set_expr(&mut self, expr: &Set) -> RTResult {
-        let object = self.evalute(&expr.object)?;
-        let mut o_b = object.borrow_mut();
-        if let Obj::Instance(ref mut i) = o_b.deref_mut() {
-            let value = self.evalute(&expr.value)?;
-            Ok(i.set(&expr, value)?)
+    fn visit_conditional_expr(&mut self, expr: &Conditional) -> RTResult {
+        let condition = self
+            .evalute(&expr.condition)?
+            .to_bool()
+            .map_err(|_| RuntimeException::error(&expr.question, "Condition must be bool or nil."))?;
+        if condition {
+            self.evalute(&expr.then_branch)
         } else {
-            Err(RuntimeException::error(
-                    &expr.name,
-                    "Only instances have properties."
-                    ))
+            self.evalute(&expr.else_branch)
         }
     }
-    fn visit_this_expr(&mut self, expr: &This) -> RTResult {
-        self.lookup_variable(&expr.keyword)
-    }
 }
 
 impl stmt::Visitor<RTResult> for Interpreter {
@@ -327,14 +1567,22 @@ impl stmt::Visitor<RTResult> for Interpreter {
         self.evalute(&stmt.expression)
     }
     fn visit_print_stmt(&mut self, stmt: &Print) -> RTResult {
-        let obj = self.evalute(&stmt.expression).unwrap();
-        println!("{:?}", obj);
+        let obj = self.evalute(&stmt.expression)?;
+        let text = self.stringify(&obj, &stmt.keyword)?;
+        writeln!(self.out, "{}", text)
+            .map_err(|e| RuntimeException::error(&stmt.keyword, &e.to_string()))?;
         Ok(Object::NIL())
     }
     fn visit_var_stmt(&mut self, stmt: &Var) -> RTResult {
-        let obj = self.evalute(&stmt.initializer)?;
-        self.environment.define(stmt.name.lexeme.clone(), obj);
-        Ok(Object::NIL())
+        let obj = match &stmt.initializer {
+            Some(initializer) => self.evalute(initializer)?,
+            None => Object::NIL(),
+        };
+        if stmt.constant {
+            self.environment.declare_constant(&stmt.name, self.slot(&stmt.name), obj)
+        } else {
+            self.environment.declare(&stmt.name, self.slot(&stmt.name), obj)
+        }
     }
     fn visit_block_stmt(&mut self, stmt: &Block) -> RTResult {
         self.execute_block(
@@ -343,19 +1591,16 @@ impl stmt::Visitor<RTResult> for Interpreter {
         )
     }
     fn visit_if_stmt(&mut self, stmt: &If) -> RTResult {
-        let condition: bool;
         let obj = self.evalute(&stmt.condition)?;
-        match obj.to_bool() {
-            Ok(b) => {
-                condition = b;
-            }
+        let condition = match obj.to_bool() {
+            Ok(b) => b,
             Err(_) => {
                 return Err(RuntimeException::error(
                     &stmt.token,
                     "if statements condition type must be bool or nil",
                 ));
             }
-        }
+        };
         if condition {
             self.execute(&stmt.then_branch)?;
         } else if stmt.else_branch.is_some() {
@@ -365,6 +1610,7 @@ impl stmt::Visitor<RTResult> for Interpreter {
     }
     fn visit_while_stmt(&mut self, stmt: &While) -> RTResult {
         loop {
+            self.tick()?;
             let condition = self.evalute(&stmt.condition)?;
             let b = condition.to_bool().map_err(|_| {
                 RuntimeException::error(
@@ -372,32 +1618,149 @@ impl stmt::Visitor<RTResult> for Interpreter {
                     "while statements condition type must be bool or nil",
                 )
             })?;
-            if !b {
+            if !b || !self.loop_body(&stmt.body)? {
                 return Ok(Object::NIL());
-            } else {
-                self.execute(&stmt.body)?;
             }
         }
     }
     fn visit_function_stmt(&mut self, stmt: &Function) -> RTResult {
         let function = Object::Function(LoxFunction::new(stmt.clone(), self.environment.clone(), false));
-        self.environment.define(stmt.name.lexeme.clone(), function);
+        self.environment.declare(&stmt.name, self.slot(&stmt.name), function)
+    }
+    fn visit_for_stmt(&mut self, stmt: &For) -> RTResult {
+        let env = self.environment.clone();
+        self.environment = Environment::from_env(env.clone());
+        let result = self.execute_for(stmt);
+        self.environment = env;
+        result
+    }
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> RTResult {
+        let iterable = self.evalute(&stmt.iterable)?;
+        let mut iteration = self.iteration(iterable, &stmt.token)?;
+        while let Some(item) = self.next(&mut iteration, &stmt.token)? {
+            self.tick()?;
+            let mut environment = Environment::from_env(self.environment.clone());
+            environment.define_slot(self.slot(&stmt.name), stmt.name.lexeme.clone(), item);
+            let env = mem::replace(&mut self.environment, environment);
+            let result = self.loop_body(&stmt.body);
+            self.environment = env;
+            if !result? {
+                break;
+            }
+        }
         Ok(Object::NIL())
     }
+    fn visit_yield_stmt(&mut self, _stmt: &Yield) -> RTResult {
+        unreachable!("generator frames run `yield` themselves")
+    }
+    fn visit_break_stmt(&mut self, _stmt: &Break) -> RTResult {
+        Err(RuntimeException::BREAK)
+    }
+    fn visit_continue_stmt(&mut self, _stmt: &Continue) -> RTResult {
+        Err(RuntimeException::CONTINUE)
+    }
     fn visit_return_stmt(&mut self, stmt: &Return) -> RTResult {
-        let obj = self.evalute(&stmt.value)?;
+        let obj = match &stmt.value {
+            Some(value) => self.evalute(value)?,
+            None => Object::NIL(),
+        };
         Err(RuntimeException::return_v(obj))
     }
+    fn visit_import_stmt(&mut self, stmt: &Import) -> RTResult {
+        let module = self.import(&stmt.path)?;
+        if let Some(name) = &stmt.name {
+            self.environment.declare(name, self.slot(name), module.clone())?;
+        }
+        for name in stmt.names.iter() {
+            let value = match module.borrow().deref() {
+                Obj::Module(m) => m.get(name)?,
+                _ => unreachable!(),
+            };
+            self.environment.declare(name, self.slot(name), value)?;
+        }
+        Ok(Object::NIL())
+    }
+    fn visit_throw_stmt(&mut self, stmt: &Throw) -> RTResult {
+        let value = self.evalute(&stmt.value)?;
+        Err(RuntimeException::ERROR(RuntimeError::thrown(&stmt.keyword, value)))
+    }
+    fn visit_try_stmt(&mut self, stmt: &Try) -> RTResult {
+        let mut result = self.execute_block(
+            &stmt.body,
+            Environment::from_env(self.environment.clone()),
+        );
+        if let (Err(RuntimeException::ERROR(error)), Some(name)) = (&result, &stmt.name) {
+            let mut environment = Environment::from_env(self.environment.clone());
+            environment.define_slot(self.slot(name), name.lexeme.clone(), error.to_object());
+            result = self.execute_block(&stmt.catch_body, environment);
+        }
+        // An abort stops the script outright; nothing more of it may run.
+        if let (Some(finally_body), false) = (
+            &stmt.finally_body,
+            matches!(result, Err(RuntimeException::ABORT(_))),
+        ) {
+            self.execute_block(
+                finally_body,
+                Environment::from_env(self.environment.clone()),
+            )?;
+        }
+        result
+    }
+    /// Trait members are copied in first so the class's own members replace them.
     fn visit_class_stmt(&mut self, stmt: &Class) -> RTResult {
-        let mut methods = HashMap::new();
+        let mut class = LoxClass::new(stmt.name.lexeme.clone(), HashMap::new());
+        let mut provided: Vec<(FunctionKind, String, String)> = Vec::new();
+        for expr in stmt.traits.iter() {
+            let name = match expr {
+                Expr::Variable(v) => &v.name,
+                _ => unreachable!(),
+            };
+            let trait_o = self.evalute(expr)?;
+            let trait_b = trait_o.borrow();
+            let t = match trait_b.deref() {
+                Obj::Trait(t) => t,
+                _ => return Err(RuntimeException::error(name, "Class can only use traits.")),
+            };
+            for (kind, member, function) in t.members() {
+                let overridden = stmt.methods.iter().any(|m| m.kind == kind && &m.name.lexeme == member);
+                if let Some((_, _, other)) = provided.iter().find(|(k, m, _)| *k == kind && m == member) {
+                    if !overridden {
+                        return Err(RuntimeException::error(
+                            name,
+                            &format!("Method '{}' is provided by both '{}' and '{}'.", member, other, t.name()),
+                        ));
+                    }
+                }
+                provided.push((kind, member.clone(), t.name().to_string()));
+                class.add_member(kind, member.clone(), function.clone());
+            }
+        }
         for method in stmt.methods.iter() {
             let name = method.name.lexeme.clone();
-            let function = Object::Function(LoxFunction::new(method.clone(), self.environment.clone(), name == "init"));
-            methods.insert(name, function);
+            let is_initializer = method.kind == FunctionKind::METHOD && name == "init";
+            let function = Object::Function(LoxFunction::new(method.clone(), self.environment.clone(), is_initializer));
+            class.add_member(method.kind, name, function);
         }
-        let class = Object::Class(LoxClass::new(stmt.name.lexeme.clone(), methods));
-        self.environment.define(stmt.name.lexeme.clone(), class);
-        Ok(Object::NIL())
+        self.environment.declare(&stmt.name, self.slot(&stmt.name), Object::Class(class))
+    }
+    fn visit_trait_stmt(&mut self, stmt: &Trait) -> RTResult {
+        let mut t = LoxTrait::new(stmt.name.lexeme.clone());
+        for method in stmt.methods.iter() {
+            let function = Object::Function(LoxFunction::new(method.clone(), self.environment.clone(), false));
+            t.add_member(method.kind, method.name.lexeme.clone(), function);
+        }
+        self.environment.declare(&stmt.name, self.slot(&stmt.name), Object::Trait(t))
+    }
+    fn visit_enum_stmt(&mut self, stmt: &Enum) -> RTResult {
+        let variants = stmt
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(ordinal, variant)| {
+                Object::EnumVariant(EnumVariant::new(stmt.name.lexeme.clone(), variant.lexeme.clone(), ordinal))
+            })
+            .collect();
+        self.environment.declare(&stmt.name, self.slot(&stmt.name), Object::Enum(LoxEnum::new(stmt.name.lexeme.clone(), variants)))
     }
 }
 
@@ -423,3 +1786,271 @@ mod tests {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    /// An interpreter reading `input` and writing into the returned output
+    /// and diagnostics buffers.
+    fn buffered(input: &str) -> (Interpreter, Buffer, Buffer) {
+        let out = Buffer::default();
+        let err = Buffer::default();
+        let interpreter = Interpreter::with_io(
+            Box::new(out.clone()),
+            Box::new(err.clone()),
+            Box::new(io::Cursor::new(input.to_string())),
+        );
+        (interpreter, out, err)
+    }
+
+    /// Scans, parses, resolves and runs `source` as one compilation unit.
+    fn execute(interpreter: &mut Interpreter, source: &str) -> Result<(), RuntimeException> {
+        let statements = interpreter.compile(&source.to_string()).unwrap();
+        interpreter.interpret(statements)
+    }
+
+    fn run(source: &str, input: &str) -> (String, String) {
+        let (mut interpreter, out, err) = buffered(input);
+        let _ = execute(&mut interpreter, source);
+        (out.contents(), err.contents())
+    }
+
+    #[test]
+    fn captures_print_and_diagnostics_separately() {
+        let (out, err) = run("print 1 == 1;\nprint nil + 1;", "");
+        assert_eq!(out, "true\n");
+        assert_eq!(err, "Operands must be two numbers or two strings.\n[line 2] in script\n");
+    }
+
+    #[test]
+    fn reports_compile_errors_and_warnings_to_diagnostics() {
+        let (mut interpreter, out, err) = buffered("");
+        assert!(interpreter.compile(&"print 1 +;".to_string()).is_err());
+        assert!(interpreter.compile(&"return 1;".to_string()).is_err());
+        let statements = interpreter
+            .compile(&"print match (1) { _ => \"any\", 1 => \"one\" };".to_string())
+            .unwrap();
+        interpreter.interpret(statements).unwrap();
+        assert_eq!(out.contents(), "any\n");
+        assert_eq!(
+            err.contents(),
+            "[line 1] Error at ';': Expect expression.\n\
+             [line 1] Error at 'return': Cannot return from top-level code.\n\
+             [line 1] Warning at '1': Unreachable match arm.\n"
+        );
+    }
+
+    #[test]
+    fn too_many_arguments_does_not_hide_later_errors() {
+        let (mut interpreter, _, err) = buffered("");
+        let source = format!("f({});\nprint 1 +;", vec!["1"; 256].join(", "));
+        assert!(interpreter.compile(&source).is_err());
+        assert_eq!(
+            err.contents(),
+            "[line 1] Error at '1': Cannot have more than 255 arguments.\n\
+             [line 2] Error at ';': Expect expression.\n"
+        );
+    }
+
+    #[test]
+    fn displaying_a_cyclic_list_elides_the_repeat() {
+        let (mut interpreter, _, _) = buffered("");
+        execute(&mut interpreter, "var l = [1, {}]; l[0] = l; l[1][\"l\"] = l;").unwrap();
+        let l = interpreter.globals.get(&Token::new(TokenType::IDENTIFIER, "l".to_string(), None, 1)).unwrap();
+        assert_eq!(l.to_string(), "[[...], {l: [...]}]");
+    }
+
+    #[test]
+    fn backtrace_lists_each_call_site_down_to_script() {
+        let source = "class A {\n  m() {\n    return nil + 1;\n  }\n}\nfun outer() {\n  A().m();\n}\nouter();";
+        let (_, err) = run(source, "");
+        assert_eq!(
+            err,
+            "Operands must be two numbers or two strings.\n[line 3] in m()\n[line 7] in outer()\n[line 9] in script\n"
+        );
+    }
+
+    /// Unbounded recursion must raise a catchable error rather than
+    /// overflow the native stack of an ordinary 8MB thread.
+    #[test]
+    fn default_call_depth_fits_an_8mb_stack() {
+        let caught = thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(|| {
+                let mut interpreter = Interpreter::new();
+                let source = "fun f() { f(); } var caught; try { f(); } catch (e) { caught = e.message; }";
+                execute(&mut interpreter, source).unwrap();
+                let name = Token::new(TokenType::IDENTIFIER, "caught".to_string(), None, 1);
+                interpreter.globals.get(&name).unwrap().to_string()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(caught, "Stack overflow.");
+    }
+
+    #[test]
+    fn max_call_depth_is_configurable() {
+        let (mut interpreter, _, err) = buffered("");
+        interpreter.set_max_call_depth(10);
+        let result = execute(&mut interpreter, "fun f(n) { if (n > 0) f(n - 1); } f(5); f(20);");
+        assert!(matches!(result, Err(RuntimeException::ERROR(_))));
+        assert!(err.contents().starts_with("Stack overflow.\n"));
+    }
+
+    /// Each source is scanned on its own, as REPL lines are, so resolutions
+    /// from one must not clobber those of another.
+    #[test]
+    fn resolves_locals_across_separately_scanned_sources() {
+        let (mut interpreter, out, _) = buffered("");
+        execute(&mut interpreter, "fun make(a) { fun get() { return a; } return get; } var first = make(\"first\");").unwrap();
+        execute(&mut interpreter, "{ var b = \"b\"; var c = \"second\"; print c; }").unwrap();
+        execute(&mut interpreter, "fun again(x) { var y = x; return y; } print first(); print again(\"third\");").unwrap();
+        assert_eq!(out.contents(), "second\nfirst\nthird\n");
+    }
+
+    #[test]
+    fn global_constants_cannot_be_redeclared_by_later_sources() {
+        let (mut interpreter, out, err) = buffered("");
+        execute(&mut interpreter, "const X = 1;").unwrap();
+        for source in ["var X = 2;", "fun X() {}", "class X {}", "const X = 2;"] {
+            assert!(execute(&mut interpreter, source).is_err());
+        }
+        execute(&mut interpreter, "print X;").unwrap();
+        assert_eq!(out.contents(), "1\n");
+        assert_eq!(err.contents(), "Cannot redefine constant 'X'.\n[line 1] in script\n".repeat(4));
+    }
+
+    #[test]
+    fn reads_lines_from_input() {
+        let (out, _) = run("print readLine(); print readLine(); print readLine();", "a\nb\n");
+        assert_eq!(out, "a\nb\nnil\n");
+    }
+    #[test]
+    fn try_does_not_catch_aborts() {
+        let source = "try { while (true) {} } catch (e) { print \"caught\"; } finally { print \"finally\"; }";
+        let (mut interpreter, out, err) = buffered("");
+        interpreter.set_step_limit(Some(1000));
+        let result = execute(&mut interpreter, source);
+        assert!(matches!(result, Err(RuntimeException::ABORT(AbortReason::BUDGET))));
+        assert_eq!(out.contents(), "");
+        assert_eq!(err.contents(), "Execution step budget exhausted.\n");
+    }
+
+    #[test]
+    fn deadline_aborts_an_endless_loop() {
+        let (mut interpreter, out, err) = buffered("");
+        interpreter.set_timeout(Some(Duration::from_millis(20)));
+        let result = execute(&mut interpreter, "try { while (true) {} } catch (e) { print \"caught\"; }");
+        assert!(matches!(result, Err(RuntimeException::ABORT(AbortReason::TIMEOUT))));
+        assert_eq!(out.contents(), "");
+        assert_eq!(err.contents(), "Execution timed out.\n");
+    }
+
+    #[test]
+    fn interrupt_handle_stops_a_running_script() {
+        let (mut interpreter, out, err) = buffered("");
+        let handle = interpreter.interrupt_handle();
+        let interrupter = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            handle.interrupt();
+        });
+        let result = execute(&mut interpreter, "try { while (true) {} } catch (e) { print \"caught\"; }");
+        interrupter.join().unwrap();
+        assert!(matches!(result, Err(RuntimeException::ABORT(AbortReason::INTERRUPT))));
+        assert_eq!(out.contents(), "");
+        assert_eq!(err.contents(), "Execution interrupted.\n");
+    }
+
+    #[test]
+    fn interrupt_between_scripts_does_not_abort_the_next() {
+        let (mut interpreter, out, _) = buffered("");
+        interpreter.interrupt_handle().interrupt();
+        assert!(execute(&mut interpreter, "print 1;").is_ok());
+        assert_eq!(out.contents(), "1\n");
+    }
+
+    #[test]
+    fn filling_a_map_hits_the_memory_limit() {
+        let (mut interpreter, _, err) = buffered("");
+        interpreter.set_memory_limit(Some(interpreter.heap_stats().bytes() + 64 * 1024));
+        let result = execute(&mut interpreter, "var m = {}; var i = 0; while (true) { m[i] = true; i = i + 1; }");
+        assert!(matches!(result, Err(RuntimeException::ERROR { .. })));
+        assert_eq!(err.contents(), "Out of memory.\n[line 1] in script\n");
+    }
+
+    #[test]
+    fn interpreters_on_one_thread_have_their_own_heap() {
+        let (mut big, _, _) = buffered("");
+        let (mut small, out, err) = buffered("");
+        small.set_memory_limit(Some(small.heap_stats().bytes() + 64 * 1024));
+        let before = small.heap_stats().bytes();
+        execute(&mut big, "var m = {}; for (var i in range(1000)) m[i] = true;").unwrap();
+        assert_eq!(small.heap_stats().bytes(), before);
+        assert!(big.heap_stats().bytes() > before + 64 * 1024);
+        execute(&mut small, "print gcStats().map.objects;").unwrap();
+        assert_eq!(err.contents(), "");
+        assert_eq!(out.contents(), "0\n");
+    }
+
+    #[test]
+    fn map_entries_and_instance_fields_are_counted() {
+        let source = "class P {}
+            var m = {}; var p = P();
+            gc(); var maps = gcStats().map.bytes; var instances = gcStats().instance.bytes;
+            for (var i in range(100)) m[i] = nil;
+            p.a = nil; p.b = nil; p.c = nil; p.d = nil;
+            gc();
+            print gcStats().map.bytes > maps;
+            print gcStats().instance.bytes > instances;";
+        let (out, err) = run(source, "");
+        assert_eq!(err, "");
+        assert_eq!(out, "true\ntrue\n");
+    }
+
+    #[test]
+    fn gc_returns_the_number_of_objects_freed() {
+        let source = "fun garbage() { var l = [1, 2, 3]; } var keep = [4];
+            gc(); garbage(); print gc() >= 4; print gcStats().list.objects;";
+        let (out, err) = run(source, "");
+        assert_eq!(err, "");
+        assert_eq!(out, "true\n1\n");
+    }
+
+    #[test]
+    fn gc_stats_report_totals_limit_and_kinds() {
+        let source = "var l = [1, 2];
+            var s = gcStats();
+            print s.objects >= 3;
+            print s.bytes >= s.list.bytes + s.integer.bytes;
+            print s.list.objects;
+            print s.limit;";
+        let (out, err) = run(source, "");
+        assert_eq!(err, "");
+        assert_eq!(out, "true\ntrue\n1\nnil\n");
+        let (mut interpreter, out, _) = buffered("");
+        interpreter.set_memory_limit(Some(1 << 20));
+        execute(&mut interpreter, "print gcStats().limit;").unwrap();
+        assert_eq!(out.contents(), "1048576\n");
+    }
+}
@@ -0,0 +1,33 @@
+#![allow(
+    non_snake_case,
+    non_local_definitions,
+    clippy::upper_case_acronyms,
+    clippy::new_ret_no_self,
+    clippy::result_unit_err,
+    clippy::result_large_err
+)]
+pub mod error;
+pub mod expr;
+pub mod heap;
+pub mod parser;
+pub mod scanner;
+pub mod token;
+pub mod tokentype;
+pub mod ast_printer;
+pub mod dump;
+pub mod environment;
+pub mod interpreter;
+pub mod lox_class;
+pub mod lox_enum;
+pub mod lox_function;
+pub mod lox_generator;
+pub mod lox_instance;
+pub mod lox_map;
+pub mod lox_module;
+pub mod lox_range;
+pub mod lox_trait;
+pub mod native;
+pub mod object;
+pub mod pattern;
+pub mod resolver;
+pub mod stmt;
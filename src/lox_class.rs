@@ -1,49 +1,114 @@
 use crate::interpreter::{Interpreter, RTResult};
-use crate::lox_function::Callable;
+use crate::lox_function::{Arity, Callable};
 use crate::lox_instance::LoxInstance;
 use crate::object::{Object, Obj};
+use crate::tokentype::FunctionKind;
 use gc_derive::{Finalize, Trace};
 use std::collections::HashMap;
+use std::fmt;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Trace, Finalize, Debug, Clone)]
 pub struct LoxClass {
+    /// Identifies the class across the copies held by its instances.
+    id: usize,
     name: String,
     methods: HashMap<String, Object>,
+    statics: HashMap<String, Object>,
+    getters: HashMap<String, Object>,
+    setters: HashMap<String, Object>,
 }
 
 impl LoxClass {
     pub fn new(name: String, methods: HashMap<String, Object>) -> LoxClass {
-        LoxClass { name , methods }
+        LoxClass {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name,
+            methods,
+            statics: HashMap::new(),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
+        }
+    }
+
+    pub fn add_member(&mut self, kind: FunctionKind, name: String, function: Object) {
+        let members = match kind {
+            FunctionKind::STATIC => &mut self.statics,
+            FunctionKind::GETTER => &mut self.getters,
+            FunctionKind::SETTER => &mut self.setters,
+            FunctionKind::FUNCTION | FunctionKind::METHOD => &mut self.methods,
+        };
+        members.insert(name, function);
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
     }
 
     pub fn find_method(&self, name: &String) -> Option<Object> {
         self.methods.get(name).cloned()
     }
+
+    pub fn find_static(&self, name: &String) -> Option<Object> {
+        self.statics.get(name).cloned()
+    }
+
+    pub fn find_getter(&self, name: &String) -> Option<Object> {
+        self.getters.get(name).cloned()
+    }
+
+    pub fn find_setter(&self, name: &String) -> Option<Object> {
+        self.setters.get(name).cloned()
+    }
+}
+
+impl fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
 }
 
 impl Callable for LoxClass {
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+        self.call_partial(interpreter, arguments.into_iter().map(Some).collect())
+    }
+
+    fn call_partial(&self, interpreter: &mut Interpreter, arguments: Vec<Option<Object>>) -> RTResult {
         let instance = Object::Instance(LoxInstance::new(self.clone()));
         if let Some(initializer) = self.find_method(&"init".to_string()) {
             if let Obj::Function(initializer) = initializer.borrow().deref() {
                 let bind_method = initializer.bind(instance.clone());
                 let bm = bind_method.borrow();
                 if let Obj::Function(m) = bm.deref() {
-                    m.call(interpreter, arguments)?;
+                    m.call_partial(interpreter, arguments)?;
                 } else { unreachable!() }
             } else { unreachable!() }
         }
         Ok(instance)
     }
 
-    fn arity(&self) -> usize {
+    fn arity(&self) -> Arity {
         if let Some(initializer) = self.find_method(&"init".to_string()){
             if let Obj::Function(init) = initializer.borrow().deref() {
                 init.arity()
             } else { unreachable!() }
         } else {
-            0
+            Arity::exactly(0)
+        }
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn params(&self) -> Vec<String> {
+        match self.find_method(&"init".to_string()) {
+            Some(initializer) => match initializer.borrow().deref() {
+                Obj::Function(init) => init.params(),
+                _ => unreachable!(),
+            },
+            None => Vec::new(),
         }
     }
 }
@@ -0,0 +1,71 @@
+use crate::interpreter::{RTResult, RuntimeException};
+use crate::object::{Obj, Object};
+use crate::token::Token;
+use gc_derive::{Finalize, Trace};
+use std::fmt;
+use std::ops::Deref;
+
+/// An `enum` declaration: its variants are created once, so comparing them
+/// compares identity.
+#[derive(Trace, Finalize, Debug, Clone)]
+pub struct LoxEnum {
+    name: String,
+    variants: Vec<Object>,
+}
+
+impl LoxEnum {
+    pub fn new(name: String, variants: Vec<Object>) -> LoxEnum {
+        LoxEnum { name, variants }
+    }
+    pub fn get(&self, name: &Token) -> RTResult {
+        self.variants
+            .iter()
+            .find(|variant| matches!(variant.borrow().deref(), Obj::EnumVariant(v) if v.name == name.lexeme))
+            .cloned()
+            .ok_or_else(|| {
+                RuntimeException::error(
+                    name,
+                    &format!("Enum '{}' has no variant '{}'.", self.name, name.lexeme),
+                )
+            })
+    }
+    pub fn variants(&self) -> Vec<Object> {
+        self.variants.clone()
+    }
+}
+
+impl fmt::Display for LoxEnum {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<enum {}>", self.name)
+    }
+}
+
+/// One value of an enum, with its `name` and its `ordinal` position.
+#[derive(Trace, Finalize, Debug, Clone)]
+pub struct EnumVariant {
+    enum_name: String,
+    name: String,
+    ordinal: usize,
+}
+
+impl EnumVariant {
+    pub fn new(enum_name: String, name: String, ordinal: usize) -> EnumVariant {
+        EnumVariant { enum_name, name, ordinal }
+    }
+    pub fn get(&self, name: &Token) -> RTResult {
+        match name.lexeme.as_str() {
+            "name" => Ok(Object::STRING(self.name.clone())),
+            "ordinal" => Ok(Object::INTEGER(self.ordinal as i64)),
+            _ => Err(RuntimeException::error(
+                name,
+                &format!("Undefined property '{}'.", name.lexeme),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EnumVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.enum_name, self.name)
+    }
+}
@@ -1,13 +1,53 @@
 use crate::environment::Environment;
 use crate::interpreter::{Interpreter, RTResult, RuntimeException};
-use crate::object::Object;
+use crate::lox_generator::{contains_yield, LoxGenerator};
+use crate::object::{Obj, Object};
 use crate::stmt::Function;
 use gc_derive::{Finalize, Trace};
 use std::fmt;
+use std::mem;
+use std::ops::Deref;
+use std::rc::Rc;
 
 pub trait Callable {
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult;
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
+    fn name(&self) -> String;
+    /// Names of the parameters named arguments can bind to.
+    fn params(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Calls with one slot per parameter; an empty slot takes its default.
+    fn call_partial(&self, interpreter: &mut Interpreter, arguments: Vec<Option<Object>>) -> RTResult {
+        self.call(interpreter, arguments.into_iter().flatten().collect())
+    }
+}
+
+/// How many arguments a callable takes; `max` is `None` with a rest parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exactly(n: usize) -> Arity {
+        Arity { min: n, max: Some(n) }
+    }
+
+    pub fn accepts(&self, n: usize) -> bool {
+        n >= self.min && self.max.is_none_or(|max| n <= max)
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
 }
 
 // #[derive(Debug, Clone)]
@@ -28,9 +68,14 @@ pub trait Callable {
 #[derive(Trace, Finalize, Clone)]
 pub struct LoxFunction {
     #[unsafe_ignore_trace]
-    declaration: Function, // 该项不会含有gc管理的对象
+    declaration: Rc<Function>, // 该项不会含有gc管理的对象
     closure: Environment,
+    /// Globals of the file the function was declared in, which its
+    /// unresolved names refer to wherever it is called from.
+    globals: Environment,
     is_initializer: bool,
+    /// Whether the body yields, making each call return a generator.
+    is_generator: bool,
 }
 
 impl fmt::Debug for LoxFunction {
@@ -39,6 +84,12 @@ impl fmt::Debug for LoxFunction {
     }
 }
 
+impl fmt::Display for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name.lexeme)
+    }
+}
+
 // impl Clone for LoxFunction {
 //     fn clone(&self) -> Self {
 //         let closure = self.closure.to_strong();
@@ -52,7 +103,9 @@ impl fmt::Debug for LoxFunction {
 impl LoxFunction {
     pub fn new(declaration: Function, env: Environment, is_initializer: bool) -> LoxFunction {
         LoxFunction {
-            declaration,
+            is_generator: contains_yield(&declaration.body),
+            declaration: Rc::new(declaration),
+            globals: env.root(),
             closure: env,
             is_initializer,
         }
@@ -61,7 +114,22 @@ impl LoxFunction {
     pub fn bind(&self, instance: Object) -> Object {
         let mut env = Environment::from_env(self.closure.clone());
         env.define("this".to_string(), instance);
-        Object::Function(LoxFunction::new(self.declaration.clone(), env, self.is_initializer))  // clone declaration is expensive
+        Object::Function(LoxFunction {
+            declaration: self.declaration.clone(),
+            closure: env,
+            globals: self.globals.clone(),
+            is_initializer: self.is_initializer,
+            is_generator: self.is_generator,
+        })
+    }
+}
+
+/// Binds `this` in a method held as an object.
+pub fn bind(method: &Object, this: Object) -> Object {
+    match method.borrow().deref() {
+        Obj::Function(m) => m.bind(this),
+        Obj::Native(m) => Object::Native(m.bind(this)),
+        _ => unreachable!(),
     }
 }
 
@@ -71,17 +139,51 @@ impl LoxFunction {
 //     }
 // }
 
+impl LoxFunction {
+    /// Defines the parameters, evaluating defaults for missing arguments in
+    /// the call's scope so they can refer to earlier parameters.
+    fn bind_arguments(&self, interpreter: &mut Interpreter, arguments: Vec<Option<Object>>) -> Result<Environment, RuntimeException> {
+        let mut environment = Environment::from_env(self.closure.clone());
+        let mut arguments = arguments.into_iter();
+        for (param, default) in self.declaration.params.iter().zip(self.declaration.defaults.iter()) {
+            let value = match (arguments.next().flatten(), default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => interpreter.evaluate_in(default, environment.clone())?,
+                (None, None) => unreachable!(),
+            };
+            environment.define_slot(interpreter.slot(param), param.lexeme.clone(), value);
+        }
+        if let Some(rest) = &self.declaration.rest {
+            environment.define_slot(interpreter.slot(rest), rest.lexeme.clone(), Object::List(arguments.flatten().collect()));
+        }
+        Ok(environment)
+    }
+}
+
 impl Callable for LoxFunction {
     fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
-        let mut environment = Environment::from_env(self.closure.clone());
-        for (i, param) in self.declaration.params.iter().enumerate() {
-            environment.define(param.lexeme.clone(), arguments[i].clone());
+        self.call_partial(interpreter, arguments.into_iter().map(Some).collect())
+    }
+    fn call_partial(&self, interpreter: &mut Interpreter, arguments: Vec<Option<Object>>) -> RTResult {
+        let globals = mem::replace(&mut interpreter.globals, self.globals.clone());
+        if self.is_generator {
+            let result = self.bind_arguments(interpreter, arguments);
+            interpreter.globals = globals;
+            return Ok(Object::Generator(LoxGenerator::new(
+                self.name(),
+                self.declaration.body.clone(),
+                result?,
+                self.globals.clone(),
+            )));
         }
-        // println!("func: {:?}\n", environment);
-        match interpreter.execute_block(&self.declaration.body, environment) {
+        let result = self
+            .bind_arguments(interpreter, arguments)
+            .and_then(|environment| interpreter.execute_block(&self.declaration.body, environment));
+        interpreter.globals = globals;
+        match result {
             Ok(obj) => {
                 if self.is_initializer {
-                    self.closure.get_at(0, &"this".to_string())
+                    self.closure.get_at(0, 0)
                 } else {
                     Ok(obj)
                 }
@@ -89,7 +191,7 @@ impl Callable for LoxFunction {
             Err(exception) => match exception {
                 RuntimeException::RETURN(rv) => {
                     if self.is_initializer {
-                        self.closure.get_at(0, &"this".to_string())
+                        self.closure.get_at(0, 0)
                     } else {
                         Ok(rv.value)
                     }
@@ -99,7 +201,17 @@ impl Callable for LoxFunction {
         }
         // Ok(Object::NIL(None))
     }
-    fn arity(&self) -> usize {
-        self.declaration.params.len()
+    fn arity(&self) -> Arity {
+        let params = self.declaration.params.len();
+        Arity {
+            min: self.declaration.defaults.iter().filter(|d| d.is_none()).count(),
+            max: if self.declaration.rest.is_some() { None } else { Some(params) },
+        }
+    }
+    fn name(&self) -> String {
+        self.declaration.name.lexeme.clone()
+    }
+    fn params(&self) -> Vec<String> {
+        self.declaration.params.iter().map(|p| p.lexeme.clone()).collect()
     }
 }
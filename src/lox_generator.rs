@@ -0,0 +1,130 @@
+use crate::environment::Environment;
+use crate::interpreter::{Iteration, RuntimeException};
+use crate::stmt::{ForIn, Stmt, Try};
+use gc_derive::{Finalize, Trace};
+use std::fmt;
+use std::rc::Rc;
+
+/// Whether a function body makes its function a generator. `yield` is a
+/// statement, so a generator only ever suspends between statements; nested
+/// functions and classes have bodies of their own.
+pub fn contains_yield(statements: &[Stmt]) -> bool {
+    statements.iter().any(yields)
+}
+
+/// Whether running `stmt` may suspend its generator.
+pub fn yields(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Yield(_) => true,
+        Stmt::Block(block) => contains_yield(&block.statements),
+        Stmt::If(s) => yields(&s.then_branch) || s.else_branch.as_deref().is_some_and(yields),
+        Stmt::While(s) => yields(&s.body),
+        Stmt::For(s) => yields(&s.body),
+        Stmt::ForIn(s) => yields(&s.body),
+        Stmt::Try(s) => {
+            contains_yield(&s.body)
+                || contains_yield(&s.catch_body)
+                || s.finally_body.as_deref().is_some_and(contains_yield)
+        }
+        _ => false,
+    }
+}
+
+/// Where a `try` statement in a generator is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    START,
+    BODY,
+    CATCH,
+    FINALLY,
+}
+
+/// A statement a generator is suspended inside of. Statements that cannot
+/// yield run to completion as usual, so only these need their own frame.
+#[allow(non_camel_case_types)]
+#[derive(Trace, Finalize, Debug)]
+pub enum Frame {
+    /// Statements run in `env`; `next` is the index of the next one.
+    BLOCK {
+        #[unsafe_ignore_trace]
+        statements: Vec<Stmt>,
+        next: usize,
+        env: Environment,
+    },
+    /// A `while` or `for` loop, between passes of its body. A `for` loop's
+    /// increment runs once `started`.
+    LOOP {
+        #[unsafe_ignore_trace]
+        stmt: Stmt,
+        env: Environment,
+        started: bool,
+    },
+    FOR_IN {
+        #[unsafe_ignore_trace]
+        stmt: Rc<ForIn>,
+        iteration: Iteration,
+        env: Environment,
+    },
+    /// `pending` is what the `finally` block interrupted, raised again once
+    /// it completes.
+    TRY {
+        #[unsafe_ignore_trace]
+        stmt: Rc<Try>,
+        env: Environment,
+        #[unsafe_ignore_trace]
+        phase: Phase,
+        #[unsafe_ignore_trace]
+        pending: Option<RuntimeException>,
+    },
+}
+
+/// A suspended call of a generator function, resumed by its `next()` method.
+#[derive(Trace, Finalize, Debug)]
+pub struct LoxGenerator {
+    name: String,
+    /// Globals of the file the function was declared in.
+    globals: Environment,
+    /// Innermost last; empty once the body has finished.
+    frames: Vec<Frame>,
+    running: bool,
+}
+
+impl LoxGenerator {
+    pub fn new(name: String, body: Vec<Stmt>, env: Environment, globals: Environment) -> LoxGenerator {
+        LoxGenerator {
+            name,
+            globals,
+            frames: vec![Frame::BLOCK {
+                statements: body,
+                next: 0,
+                env,
+            }],
+            running: false,
+        }
+    }
+    /// Takes the frames to run them; `None` if the generator is already
+    /// running, i.e. it called its own `next()`.
+    pub fn start(&mut self) -> Option<(Vec<Frame>, Environment)> {
+        if self.running {
+            return None;
+        }
+        self.running = true;
+        Some((std::mem::take(&mut self.frames), self.globals.clone()))
+    }
+    /// Whether the body has finished; a running generator has not.
+    pub fn is_done(&self) -> bool {
+        !self.running && self.frames.is_empty()
+    }
+    /// Puts back the frames left after a `yield`; a generator that finished
+    /// or failed has none.
+    pub fn suspend(&mut self, frames: Vec<Frame>) {
+        self.running = false;
+        self.frames = frames;
+    }
+}
+
+impl fmt::Display for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<generator {}>", self.name)
+    }
+}
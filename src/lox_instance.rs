@@ -4,6 +4,8 @@ use gc_derive::{Finalize, Trace};
 use std::collections::HashMap;
 use crate::interpreter::{RTResult, RuntimeException};
 use crate::expr::{Get, Set};
+use std::fmt;
+use std::mem;
 use std::ops::Deref;
 
 #[derive(Trace, Finalize, Debug, Clone)]
@@ -19,13 +21,14 @@ impl LoxInstance {
             fields: HashMap::new(),
         }
     }
-    pub fn get(&self, expr: &Get) -> RTResult {
+    /// `this` is the object holding this instance, which methods are bound to.
+    pub fn get(&self, expr: &Get, this: Object) -> RTResult {
         if let Some(obj) = self.fields.get(&expr.name.lexeme) {
             Ok(obj.clone())
         } else if let Some(method) = self.class.find_method(&expr.name.lexeme) {
             match method.borrow().deref() {
                 Obj::Function(m) => {
-                    Ok(m.bind(Object::Instance(self.clone())))
+                    Ok(m.bind(this))
                 }
                 _ => unreachable!()
             }
@@ -37,7 +40,41 @@ impl LoxInstance {
         }
     }
     pub fn set(&mut self, expr: &Set, value: Object) -> RTResult {
-        self.fields.insert(expr.name.lexeme.clone(), value);
+        self.set_field(expr.name.lexeme.clone(), value);
         Ok(Object::NIL())
     }
+    pub fn set_field(&mut self, name: String, value: Object) {
+        self.fields.insert(name, value);
+    }
+    /// Bytes held by the fields, for heap accounting.
+    pub fn size(&self) -> usize {
+        self.fields.capacity() * mem::size_of::<(String, Object)>()
+    }
+    pub fn field(&self, name: &str) -> Option<Object> {
+        self.fields.get(name).cloned()
+    }
+    pub fn method(&self, name: &String) -> Option<Object> {
+        self.class.find_method(name)
+    }
+    /// The getter run when reading `name`, unless a field shadows it.
+    pub fn getter(&self, name: &String) -> Option<Object> {
+        if self.fields.contains_key(name) {
+            None
+        } else {
+            self.class.find_getter(name)
+        }
+    }
+    pub fn setter(&self, name: &String) -> Option<Object> {
+        self.class.find_setter(name)
+    }
+    /// Classes are compared by id, as they are copied into each instance.
+    pub fn is_instance_of(&self, class: &LoxClass) -> bool {
+        self.class.id() == class.id()
+    }
+}
+
+impl fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class)
+    }
 }
@@ -0,0 +1,102 @@
+use crate::interpreter::{RTResult, RuntimeException};
+use crate::object::{Obj, Object};
+use crate::token::Token;
+use gc_derive::{Finalize, Trace};
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::ops::Deref;
+
+/// The hashable form of a map key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum MapKey {
+    STRING(String),
+    NUMBER(u64),
+    INTEGER(i64),
+    BOOL(bool),
+    NIL,
+}
+
+impl MapKey {
+    fn new(key: &Object, bracket: &Token) -> Result<MapKey, RuntimeException> {
+        match key.borrow().deref() {
+            Obj::STRING(s) => Ok(MapKey::STRING(s.clone())),
+            Obj::INTEGER(n) => Ok(MapKey::INTEGER(*n)),
+            // Whole floats share their integer's entry, and `+ 0.0` turns -0
+            // into 0 so both find the same entry.
+            Obj::NUMBER(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => Ok(MapKey::INTEGER(*n as i64)),
+            Obj::NUMBER(n) => Ok(MapKey::NUMBER((n + 0.0).to_bits())),
+            Obj::BOOL(b) => Ok(MapKey::BOOL(*b)),
+            Obj::NIL(_) => Ok(MapKey::NIL),
+            _ => Err(RuntimeException::error(
+                bracket,
+                "Map keys must be strings, numbers, bools or nil.",
+            )),
+        }
+    }
+}
+
+/// A map literal's value: entries keyed by strings, numbers, bools or nil,
+/// kept in insertion order.
+#[derive(Trace, Finalize, Debug, Clone, Default)]
+pub struct LoxMap {
+    #[unsafe_ignore_trace]
+    index: HashMap<MapKey, usize>,
+    keys: Vec<Object>,
+    values: Vec<Object>,
+}
+
+impl LoxMap {
+    pub fn new() -> LoxMap {
+        LoxMap::default()
+    }
+    /// The value for `key`, or nil when it has none.
+    pub fn get(&self, key: &Object, bracket: &Token) -> RTResult {
+        let key = MapKey::new(key, bracket)?;
+        Ok(match self.index.get(&key) {
+            Some(&i) => self.values[i].clone(),
+            None => Object::NIL(),
+        })
+    }
+    pub fn set(&mut self, key: Object, value: Object, bracket: &Token) -> Result<(), RuntimeException> {
+        match self.index.get(&MapKey::new(&key, bracket)?) {
+            Some(&i) => self.values[i] = value,
+            None => {
+                self.index.insert(MapKey::new(&key, bracket)?, self.keys.len());
+                self.keys.push(key);
+                self.values.push(value);
+            }
+        }
+        Ok(())
+    }
+    pub fn keys(&self) -> &[Object] {
+        &self.keys
+    }
+    pub fn values(&self) -> &[Object] {
+        &self.values
+    }
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+    /// Bytes held by the entries, for heap accounting.
+    pub fn size(&self) -> usize {
+        (self.keys.capacity() + self.values.capacity()) * mem::size_of::<Object>()
+            + self.index.capacity() * mem::size_of::<(MapKey, usize)>()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl fmt::Display for LoxMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.keys.iter().zip(self.values.iter()).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", key, value)?;
+        }
+        write!(f, "}}")
+    }
+}
@@ -0,0 +1,33 @@
+use crate::environment::Environment;
+use crate::interpreter::{RTResult, RuntimeException};
+use crate::token::Token;
+use gc_derive::{Finalize, Trace};
+use std::fmt;
+
+/// An imported file: its top-level definitions are read through the global
+/// `Environment` it was executed in.
+#[derive(Trace, Finalize, Debug, Clone)]
+pub struct LoxModule {
+    name: String,
+    globals: Environment,
+}
+
+impl LoxModule {
+    pub fn new(name: String, globals: Environment) -> LoxModule {
+        LoxModule { name, globals }
+    }
+    pub fn get(&self, name: &Token) -> RTResult {
+        self.globals.get(name).map_err(|_| {
+            RuntimeException::error(
+                name,
+                &format!("Module '{}' has no member '{}'.", self.name, name.lexeme),
+            )
+        })
+    }
+}
+
+impl fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
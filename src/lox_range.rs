@@ -0,0 +1,87 @@
+use crate::object::{write_float, Object};
+use gc_derive::{Finalize, Trace};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// `start`, `end` and `step` of a range; integers when all three are.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone)]
+enum Bounds {
+    INTEGERS(i64, i64, i64),
+    FLOATS(f64, f64, f64),
+}
+
+/// The numbers from `start` up to, but not including, `end` in steps of
+/// `step`, produced one at a time as a `for` loop asks for them.
+#[derive(Trace, Finalize, Debug, Clone)]
+pub struct LoxRange {
+    #[unsafe_ignore_trace]
+    bounds: Bounds,
+}
+
+impl LoxRange {
+    /// `step` must not be zero.
+    pub fn integers(start: i64, end: i64, step: i64) -> LoxRange {
+        LoxRange { bounds: Bounds::INTEGERS(start, end, step) }
+    }
+    /// `step` must not be zero.
+    pub fn floats(start: f64, end: f64, step: f64) -> LoxRange {
+        LoxRange { bounds: Bounds::FLOATS(start, end, step) }
+    }
+    /// The `i`th number of the range, if it has that many.
+    pub fn get(&self, i: usize) -> Option<Object> {
+        match self.bounds {
+            Bounds::INTEGERS(start, end, step) => {
+                // Computed wide, as the offset may not fit even when the
+                // number does; numbers between the bounds always fit.
+                let value = (start as i128).checked_add((i as i128).checked_mul(step as i128)?)?;
+                let inside = if step > 0 { value < end as i128 } else { value > end as i128 };
+                if inside {
+                    i64::try_from(value).ok().map(Object::INTEGER)
+                } else {
+                    None
+                }
+            }
+            Bounds::FLOATS(start, end, step) => {
+                let value = start + i as f64 * step;
+                let inside = if step > 0.0 { value < end } else { value > end };
+                if inside {
+                    Some(Object::NUMBER(value))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    pub fn len(&self) -> usize {
+        match self.bounds {
+            Bounds::INTEGERS(start, end, step) => {
+                // In i128, so the span cannot overflow.
+                let (span, step) = (end as i128 - start as i128, step as i128);
+                let len = (span + step - step.signum()) / step;
+                usize::try_from(len.max(0)).unwrap_or(usize::MAX)
+            }
+            Bounds::FLOATS(start, end, step) => ((end - start) / step).ceil().max(0.0) as usize,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for LoxRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.bounds {
+            Bounds::INTEGERS(start, end, step) => write!(f, "range({}, {}, {})", start, end, step),
+            Bounds::FLOATS(start, end, step) => {
+                write!(f, "range(")?;
+                write_float(f, start)?;
+                write!(f, ", ")?;
+                write_float(f, end)?;
+                write!(f, ", ")?;
+                write_float(f, step)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
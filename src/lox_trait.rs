@@ -0,0 +1,39 @@
+use crate::object::Object;
+use crate::tokentype::FunctionKind;
+use gc_derive::{Finalize, Trace};
+use std::fmt;
+
+/// A `trait` declaration: members that classes `with` it copy in when they
+/// are created.
+#[derive(Trace, Finalize, Debug, Clone)]
+pub struct LoxTrait {
+    name: String,
+    #[unsafe_ignore_trace]
+    kinds: Vec<FunctionKind>,
+    members: Vec<(String, Object)>,
+}
+
+impl LoxTrait {
+    pub fn new(name: String) -> LoxTrait {
+        LoxTrait { name, kinds: Vec::new(), members: Vec::new() }
+    }
+
+    pub fn add_member(&mut self, kind: FunctionKind, name: String, function: Object) {
+        self.kinds.push(kind);
+        self.members.push((name, function));
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = (FunctionKind, &String, &Object)> {
+        self.kinds.iter().zip(self.members.iter()).map(|(kind, (name, function))| (*kind, name, function))
+    }
+}
+
+impl fmt::Display for LoxTrait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<trait {}>", self.name)
+    }
+}
@@ -1,54 +1,176 @@
-mod error;
-mod expr;
-mod parser;
-mod scanner;
-mod token;
-mod tokentype;
-// mod ast_printer;
-mod environment;
-mod interpreter;
-mod lox_class;
-mod lox_function;
-mod lox_instance;
-mod object;
-mod resolver;
-mod stmt;
-// use crate::ast_printer::AstPrinter;
-use crate::interpreter::Interpreter;
-use crate::parser::Parser;
-use crate::resolver::Resolver;
-use crate::scanner::Scanner;
+#![allow(clippy::upper_case_acronyms)]
+use lox::ast_printer;
+use lox::dump;
+use lox::error;
+use lox::interpreter::Interpreter;
+use lox::parser::Parser;
+use lox::scanner::Scanner;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io::{self, BufReader};
+use std::path::Path;
+use std::process;
+use std::thread;
+
+/// Exit codes for compile and runtime errors, as in sysexits.h.
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+
+/// Native stack for the interpreter thread, sized so that `MAX_CALL_DEPTH`
+/// is reached well before the process itself overflows.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+/// Call depth limit for scripts run by `lox`, higher than the library's
+/// default thanks to the large stack; `--max-depth=N` overrides it.
+const MAX_CALL_DEPTH: usize = 10_000;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    println!("{:?}", args);
-    let args_len: usize = args.len();
-    if args_len > 2 {
-        println!("Usage: lox [script]");
-    } else if args_len == 2 {
-        run_file(&args[1]);
+    let child = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run_main)
+        .unwrap();
+    child.join().unwrap();
+}
+
+/// Front-end output requested instead of running the script.
+enum Dump {
+    TOKENS,
+    SEXPR,
+    JSON,
+}
+
+fn run_main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("fmt") {
+        process::exit(run_fmt(&args[1..]));
+    }
+    let mut dump = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--tokens" || arg.starts_with("--ast=")) {
+        dump = match args.remove(i).as_str() {
+            "--tokens" => Some(Dump::TOKENS),
+            "--ast=sexpr" => Some(Dump::SEXPR),
+            "--ast=json" => Some(Dump::JSON),
+            _ => return usage(),
+        };
+    }
+    if let Some(dump) = dump {
+        match args.len() {
+            1 => process::exit(run_dump(&args[0], dump)),
+            _ => return usage(),
+        }
+    }
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_call_depth(MAX_CALL_DEPTH);
+    if let Some(i) = args.iter().position(|arg| arg.starts_with("--max-depth=")) {
+        match args.remove(i)["--max-depth=".len()..].parse() {
+            Ok(depth) => interpreter.set_max_call_depth(depth),
+            Err(_) => return usage(),
+        }
+    }
+    match args.len() {
+        0 => run_prompt(&mut interpreter),
+        1 => run_file(&args[0], &mut interpreter),
+        _ => usage(),
+    }
+}
+
+fn usage() {
+    println!("Usage: lox [--max-depth=N] [script]");
+    println!("       lox --tokens | --ast=sexpr | --ast=json script");
+    println!("       lox fmt [--check] files...");
+}
+
+fn run_dump(path: &str, dump: Dump) -> i32 {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return 66;
+        }
+    };
+    error::reset();
+    let mut scanner = Scanner::new(&source);
+    let tokens = scanner.scan_tokens();
+    let output = match dump {
+        Dump::TOKENS => dump::tokens_json(tokens),
+        Dump::SEXPR | Dump::JSON => {
+            let statements = match Parser::new(tokens).parse() {
+                Ok(statements) => statements,
+                Err(_) => return EX_DATAERR,
+            };
+            match dump {
+                Dump::SEXPR => dump::ast_sexpr(&statements),
+                _ => dump::ast_json(&statements),
+            }
+        }
+    };
+    print!("{}", output);
+    if error::had_error() {
+        EX_DATAERR
     } else {
-        run_prompt();
+        0
     }
 }
 
-fn run_file(path: &str) {
+/// Rewrites each file in canonical form. With `--check` nothing is written
+/// and files that are not already formatted are listed instead.
+fn run_fmt(args: &[String]) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if paths.is_empty() {
+        usage();
+        return 64;
+    }
+    let mut code = 0;
+    for path in paths {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                code = 66;
+                continue;
+            }
+        };
+        let formatted = match ast_printer::format(&source) {
+            Ok(formatted) => formatted,
+            Err(_) => {
+                code = EX_DATAERR;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", path);
+            if code == 0 {
+                code = 1;
+            }
+        } else if let Err(e) = fs::write(path, formatted) {
+            eprintln!("{}: {}", path, e);
+            code = 73;
+        }
+    }
+    code
+}
+
+fn run_file(path: &str, interpreter: &mut Interpreter) {
     match File::open(path) {
         Err(e) => println!("{:?}", e),
         Ok(file) => {
             let mut buf_reader = BufReader::new(file);
             let mut s: String = String::from("");
             buf_reader.read_to_string(&mut s).unwrap();
-            run(&s);
+            interpreter.set_script_path(Path::new(path));
+            if let Err(code) = run(&s, interpreter) {
+                process::exit(code);
+            }
         }
     }
 }
 
-fn run_prompt() {
+fn run_prompt(interpreter: &mut Interpreter) {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
     let mut buf_reader = BufReader::new(stdin);
@@ -56,23 +178,15 @@ fn run_prompt() {
         print!("> ");
         stdout.flush().unwrap();
         let mut line: String = String::from("");
-        buf_reader.read_line(&mut line).unwrap();
-        run(&line);
+        if buf_reader.read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let _ = run(&line, interpreter);
     }
 }
 
-fn run(source: &String) {
-    let mut scanner = Scanner::new(source);
-    let tokens = scanner.scan_tokens();
-    // for token in tokens {
-    //     println!("{}", token);
-    // }
-    let mut parser = Parser::new(tokens);
-    let statements = parser.parse().expect("Error");
-    // let printer = AstPrinter {};
-    // println!("{}", printer.print(&expr));
-    let mut interpreter = Interpreter::new();
-    let mut resolver = Resolver::new(&mut interpreter);
-    resolver.resolves(&statements);
-    interpreter.interpret(statements);
+fn run(source: &String, interpreter: &mut Interpreter) -> Result<(), i32> {
+    let statements = interpreter.compile(source).map_err(|_| EX_DATAERR)?;
+    // Runtime errors are reported to the interpreter's diagnostics stream.
+    interpreter.interpret(statements).map_err(|_| EX_SOFTWARE)
 }
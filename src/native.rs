@@ -0,0 +1,241 @@
+use crate::environment::Environment;
+use crate::heap::{KindStats, KINDS};
+use crate::interpreter::{Interpreter, RTResult};
+use crate::lox_class::LoxClass;
+use crate::lox_function::{Arity, Callable};
+use crate::lox_instance::LoxInstance;
+use crate::lox_range::LoxRange;
+use crate::object::{Obj, Object};
+use gc_derive::{Finalize, Trace};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+
+type NativeFn = fn(&mut Interpreter, Vec<Object>) -> RTResult;
+
+#[derive(Trace, Finalize, Debug, Clone)]
+pub struct NativeFunction {
+    #[unsafe_ignore_trace]
+    name: &'static str,
+    #[unsafe_ignore_trace]
+    arity: Arity,
+    #[unsafe_ignore_trace]
+    function: NativeFn,
+    /// The receiver of a native method, passed as the first argument.
+    this: Option<Object>,
+}
+
+impl NativeFunction {
+    pub fn new(name: &'static str, arity: usize, function: NativeFn) -> NativeFunction {
+        NativeFunction::ranged(name, arity, arity, function)
+    }
+
+    /// A native taking between `min` and `max` arguments.
+    pub fn ranged(name: &'static str, min: usize, max: usize, function: NativeFn) -> NativeFunction {
+        NativeFunction {
+            name,
+            arity: Arity { min, max: Some(max) },
+            function,
+            this: None,
+        }
+    }
+
+    pub fn bind(&self, this: Object) -> NativeFunction {
+        NativeFunction {
+            this: Some(this),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+impl Callable for NativeFunction {
+    fn call(&self, interpreter: &mut Interpreter, mut arguments: Vec<Object>) -> RTResult {
+        if let Some(this) = &self.this {
+            arguments.insert(0, this.clone());
+        }
+        (self.function)(interpreter, arguments)
+    }
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+}
+
+pub fn define_globals(globals: &mut Environment) {
+    let natives = [
+        NativeFunction::new("gc", 0, gc),
+        NativeFunction::new("gcStats", 0, gc_stats),
+        NativeFunction::new("readLine", 0, read_line),
+        NativeFunction::new("len", 1, len),
+        NativeFunction::ranged("range", 1, 3, range),
+        NativeFunction::new("int", 1, int),
+        NativeFunction::new("float", 1, float),
+    ];
+    for native in natives.iter() {
+        globals.define(native.name(), Object::Native(native.clone()));
+    }
+}
+
+/// `gc()`: forces a collection and returns the number of objects freed.
+fn gc(interpreter: &mut Interpreter, _: Vec<Object>) -> RTResult {
+    Ok(Object::INTEGER(interpreter.collect_garbage() as i64))
+}
+
+/// `gcStats()`: an object with the live `objects` and `bytes` totals, the
+/// interpreter's memory `limit` (or nil) and one `{objects, bytes}` field per
+/// kind, e.g. `gcStats().string.bytes`.
+fn gc_stats(interpreter: &mut Interpreter, _: Vec<Object>) -> RTResult {
+    let stats = interpreter.heap_stats();
+    let mut instance = stats_instance(stats.objects(), stats.bytes());
+    instance.set_field(
+        "limit".to_string(),
+        match interpreter.memory_limit() {
+            Some(limit) => Object::INTEGER(limit as i64),
+            None => Object::NIL(),
+        },
+    );
+    for (name, kind) in KINDS.iter().zip(stats.kinds.iter()) {
+        let KindStats { objects, bytes } = *kind;
+        let kind_stats = Object::Instance(stats_instance(objects, bytes));
+        instance.set_field(name.to_string(), kind_stats);
+    }
+    Ok(Object::Instance(instance))
+}
+
+/// `readLine()`: the next line of input, or nil at end of input.
+fn read_line(interpreter: &mut Interpreter, _: Vec<Object>) -> RTResult {
+    match interpreter.read_line() {
+        Ok(Some(line)) => Ok(Object::STRING(line)),
+        Ok(None) | Err(_) => Ok(Object::NIL()),
+    }
+}
+
+/// `Enum.values()`: the enum's variants in declaration order.
+pub fn enum_values() -> NativeFunction {
+    NativeFunction::new("values", 0, values)
+}
+
+fn values(_: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    match arguments[0].borrow().deref() {
+        Obj::Enum(e) => Ok(Object::List(e.variants())),
+        _ => unreachable!(),
+    }
+}
+
+/// `generator.next()`: runs the generator to its next `yield` and returns
+/// the value, or nil once it has finished; `done()` tells the two apart.
+pub fn generator_next() -> NativeFunction {
+    NativeFunction::new("next", 0, next)
+}
+
+fn next(interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    Ok(interpreter.resume(&arguments[0])?.unwrap_or_else(Object::NIL))
+}
+
+/// `generator.done()`: whether `next()` has run the body to its end.
+pub fn generator_done() -> NativeFunction {
+    NativeFunction::new("done", 0, done)
+}
+
+fn done(_: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    match arguments[0].borrow().deref() {
+        Obj::Generator(g) => Ok(Object::BOOL(g.is_done())),
+        _ => unreachable!(),
+    }
+}
+
+/// `len(value)`: the number of items in a list, map, string or range.
+fn len(interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    let len = match arguments[0].borrow().deref() {
+        Obj::List(items) => items.len(),
+        Obj::Map(map) => map.len(),
+        Obj::STRING(s) => s.chars().count(),
+        Obj::Range(range) => range.len(),
+        _ => return Err(interpreter.native_error("Can only take the length of lists, maps, strings and ranges.")),
+    };
+    Ok(Object::INTEGER(len as i64))
+}
+
+/// `range(end)`, `range(start, end)` or `range(start, end, step)`: the
+/// numbers from `start` (default 0) up to `end`, produced lazily.
+fn range(interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    let mut integers = Vec::new();
+    let mut floats = Vec::new();
+    for argument in arguments.iter() {
+        match argument.borrow().deref() {
+            Obj::INTEGER(n) => {
+                integers.push(*n);
+                floats.push(*n as f64);
+            }
+            Obj::NUMBER(n) => floats.push(*n),
+            _ => return Err(interpreter.native_error("Range bounds must be numbers.")),
+        }
+    }
+    let range = if integers.len() == floats.len() {
+        let (start, end, step) = bounds(&integers, 0, 1);
+        (step != 0).then(|| LoxRange::integers(start, end, step))
+    } else {
+        let (start, end, step) = bounds(&floats, 0.0, 1.0);
+        (step != 0.0).then(|| LoxRange::floats(start, end, step))
+    };
+    match range {
+        Some(range) => Ok(Object::Range(range)),
+        None => Err(interpreter.native_error("Range step cannot be 0.")),
+    }
+}
+
+/// `start`, `end` and `step` from the one to three arguments of `range`.
+fn bounds<T: Copy>(numbers: &[T], zero: T, one: T) -> (T, T, T) {
+    match *numbers {
+        [end] => (zero, end, one),
+        [start, end] => (start, end, one),
+        [start, end, step] => (start, end, step),
+        _ => unreachable!(),
+    }
+}
+
+/// `int(value)`: a number truncated toward zero, or a string parsed as an
+/// integer.
+fn int(interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    let value = match arguments[0].borrow().deref() {
+        Obj::INTEGER(n) => Some(*n),
+        Obj::NUMBER(n) if n.is_finite() && n.abs() < i64::MAX as f64 => Some(n.trunc() as i64),
+        Obj::NUMBER(_) => None,
+        Obj::STRING(s) => s.trim().parse().ok(),
+        _ => return Err(interpreter.native_error("Can only convert numbers and strings to integers.")),
+    };
+    match value {
+        Some(n) => Ok(Object::INTEGER(n)),
+        None => Err(interpreter.native_error(&format!("Cannot convert '{}' to an integer.", arguments[0]))),
+    }
+}
+
+/// `float(value)`: a number as a float, or a string parsed as one.
+fn float(interpreter: &mut Interpreter, arguments: Vec<Object>) -> RTResult {
+    let value = match arguments[0].borrow().deref() {
+        Obj::INTEGER(n) => Some(*n as f64),
+        Obj::NUMBER(n) => Some(*n),
+        Obj::STRING(s) => s.trim().parse().ok(),
+        _ => return Err(interpreter.native_error("Can only convert numbers and strings to floats.")),
+    };
+    match value {
+        Some(n) => Ok(Object::NUMBER(n)),
+        None => Err(interpreter.native_error(&format!("Cannot convert '{}' to a float.", arguments[0]))),
+    }
+}
+
+fn stats_instance(objects: usize, bytes: usize) -> LoxInstance {
+    let class = LoxClass::new("GcStats".to_string(), HashMap::new());
+    let mut instance = LoxInstance::new(class);
+    instance.set_field("objects".to_string(), Object::INTEGER(objects as i64));
+    instance.set_field("bytes".to_string(), Object::INTEGER(bytes as i64));
+    instance
+}
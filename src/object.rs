@@ -1,8 +1,18 @@
+use crate::heap;
 use crate::lox_class::LoxClass;
+use crate::lox_enum::{EnumVariant, LoxEnum};
 use crate::lox_function::LoxFunction;
+use crate::lox_generator::LoxGenerator;
 use crate::lox_instance::LoxInstance;
-use gc::{Gc, GcCell};
+use crate::lox_map::LoxMap;
+use crate::lox_module::LoxModule;
+use crate::lox_range::LoxRange;
+use crate::lox_trait::LoxTrait;
+use crate::native::NativeFunction;
+use gc::{Finalize, Gc, GcCell};
 use gc_derive::{Finalize, Trace};
+use std::cell::RefCell;
+use std::fmt;
 use std::ops::Deref;
 
 
@@ -12,9 +22,13 @@ pub struct Object {
 }
 
 macro_rules! ret {
-    ($e:expr) => (Object {
-        obj: Gc::new(GcCell::new($e))
-    });
+    ($e:expr) => {{
+        let obj = $e;
+        let stats = heap::allocated(&obj);
+        Object {
+            obj: Gc::new(Allocation { cell: GcCell::new(obj), stats }),
+        }
+    }};
 }
 
 impl Object {
@@ -24,6 +38,9 @@ impl Object {
     pub fn NUMBER(s: f64) -> Self {
         ret!(Obj::NUMBER(s))
     }
+    pub fn INTEGER(s: i64) -> Self {
+        ret!(Obj::INTEGER(s))
+    }
     pub fn BOOL(s: bool) -> Self {
         ret!(Obj::BOOL(s))
     }
@@ -39,9 +56,52 @@ impl Object {
     pub fn Instance(s: LoxInstance) -> Self {
         ret!(Obj::Instance(s))
     }
+    pub fn Native(s: NativeFunction) -> Self {
+        ret!(Obj::Native(s))
+    }
+    pub fn List(s: Vec<Object>) -> Self {
+        ret!(Obj::List(s))
+    }
+    pub fn Map(s: LoxMap) -> Self {
+        ret!(Obj::Map(s))
+    }
+    pub fn Range(s: LoxRange) -> Self {
+        ret!(Obj::Range(s))
+    }
+    pub fn Module(s: LoxModule) -> Self {
+        ret!(Obj::Module(s))
+    }
+    pub fn Enum(s: LoxEnum) -> Self {
+        ret!(Obj::Enum(s))
+    }
+    pub fn EnumVariant(s: EnumVariant) -> Self {
+        ret!(Obj::EnumVariant(s))
+    }
+    pub fn Generator(s: LoxGenerator) -> Self {
+        ret!(Obj::Generator(s))
+    }
+    pub fn Trait(s: LoxTrait) -> Self {
+        ret!(Obj::Trait(s))
+    }
+
+    /// Whether both refer to the same heap object.
+    pub fn is_same(&self, other: &Object) -> bool {
+        Gc::ptr_eq(&self.obj, &other.obj)
+    }
+
+    /// Records that this object, whose contents `obj` measured `before`
+    /// bytes, grew or shrank in place.
+    pub fn resized(&self, obj: &Obj, before: usize) {
+        if let Some(stats) = &self.obj.stats {
+            heap::resized(stats, obj, before);
+        }
+    }
 
+    pub fn is_nil(&self) -> bool {
+        matches!(self.obj.cell.borrow().deref(), Obj::NIL(_))
+    }
     pub fn to_bool(&self) -> Result<bool, ()> {
-        match self.obj.borrow().deref() {
+        match self.obj.cell.borrow().deref() {
             Obj::BOOL(b) => Ok(*b),
             Obj::NIL(_) => Ok(false),
             _ => Err(()),
@@ -49,23 +109,152 @@ impl Object {
     }
 }
 
+thread_local!(static PRINTING: RefCell<Vec<Object>> = const { RefCell::new(Vec::new()) });
+
+impl Object {
+    /// How a list or map is shown inside itself, or `None` for objects that
+    /// cannot contain themselves.
+    pub fn elided(&self) -> Option<&'static str> {
+        match self.obj.cell.borrow().deref() {
+            Obj::List(_) => Some("[...]"),
+            Obj::Map(_) => Some("{...}"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let elided = match self.elided() {
+            Some(elided) => elided,
+            None => return write!(f, "{}", self.obj.cell.borrow().deref()),
+        };
+        if PRINTING.with(|printing| printing.borrow().iter().any(|o| o.is_same(self))) {
+            return write!(f, "{}", elided);
+        }
+        PRINTING.with(|printing| printing.borrow_mut().push(self.clone()));
+        let result = write!(f, "{}", self.obj.cell.borrow().deref());
+        PRINTING.with(|printing| printing.borrow_mut().pop());
+        result
+    }
+}
+
 impl Deref for Object {
     type Target = GcCell<Obj>;
     #[inline]
     fn deref(&self) -> &GcCell<Obj> {
-        &self.obj
+        &self.obj.cell
+    }
+}
+
+type GcObj = Gc<Allocation>;
+
+#[derive(Trace)]
+struct Allocation {
+    cell: GcCell<Obj>,
+    /// The interpreter's heap accounting this object is counted in, if any.
+    #[unsafe_ignore_trace]
+    stats: Option<heap::Stats>,
+}
+
+impl fmt::Debug for Allocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.cell.fmt(f)
     }
 }
 
-type GcObj = Gc<GcCell<Obj>>;
+impl Finalize for Allocation {
+    fn finalize(&self) {
+        if let (Some(stats), Ok(obj)) = (&self.stats, self.cell.try_borrow()) {
+            heap::freed(stats, &obj);
+        }
+    }
+}
 
 #[derive(Trace, Finalize, Debug)]
 pub enum Obj {
     STRING(String),
     NUMBER(f64),
+    INTEGER(i64),
     BOOL(bool),
     NIL(Option<()>),
     Function(LoxFunction),
     Class(LoxClass),
     Instance(LoxInstance),
+    Native(NativeFunction),
+    Module(LoxModule),
+    List(Vec<Object>),
+    Map(LoxMap),
+    Range(LoxRange),
+    Enum(LoxEnum),
+    EnumVariant(EnumVariant),
+    Generator(LoxGenerator),
+    Trait(LoxTrait),
+}
+
+impl Obj {
+    /// Index of this variant in `heap::KINDS`.
+    pub fn kind(&self) -> usize {
+        match self {
+            Obj::STRING(_) => 0,
+            Obj::NUMBER(_) => 1,
+            Obj::INTEGER(_) => 2,
+            Obj::BOOL(_) => 3,
+            Obj::NIL(_) => 4,
+            Obj::Function(_) => 5,
+            Obj::Class(_) => 6,
+            Obj::Instance(_) => 7,
+            Obj::Native(_) => 8,
+            Obj::Module(_) => 9,
+            Obj::List(_) => 10,
+            Obj::Map(_) => 11,
+            Obj::Range(_) => 12,
+            Obj::Enum(_) => 13,
+            Obj::EnumVariant(_) => 14,
+            Obj::Generator(_) => 15,
+            Obj::Trait(_) => 16,
+        }
+    }
+}
+
+/// Writes a float so that it never reads as an integer: `3.0`, not `3`.
+pub fn write_float(f: &mut fmt::Formatter, n: f64) -> fmt::Result {
+    if n.is_finite() && n.fract() == 0.0 {
+        write!(f, "{:.1}", n)
+    } else {
+        write!(f, "{}", n)
+    }
+}
+
+impl fmt::Display for Obj {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Obj::STRING(s) => write!(f, "{}", s),
+            Obj::NUMBER(n) => write_float(f, *n),
+            Obj::INTEGER(n) => write!(f, "{}", n),
+            Obj::BOOL(b) => write!(f, "{}", b),
+            Obj::NIL(_) => write!(f, "nil"),
+            Obj::Function(func) => write!(f, "{}", func),
+            Obj::Class(class) => write!(f, "{}", class),
+            Obj::Instance(instance) => write!(f, "{}", instance),
+            Obj::Native(native) => write!(f, "{}", native),
+            Obj::Module(module) => write!(f, "{}", module),
+            Obj::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Obj::Map(map) => write!(f, "{}", map),
+            Obj::Range(range) => write!(f, "{}", range),
+            Obj::Enum(e) => write!(f, "{}", e),
+            Obj::EnumVariant(variant) => write!(f, "{}", variant),
+            Obj::Generator(generator) => write!(f, "{}", generator),
+            Obj::Trait(t) => write!(f, "{}", t),
+        }
+    }
 }
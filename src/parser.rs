@@ -1,9 +1,11 @@
 use crate::error::parse_error;
-use crate::expr::{Assign, Binary, Call, Expr, Get, Grouping, Literal, Logical, Unary, Variable, Set, This};
-use crate::stmt::{Block, Class, Expression, Function, If, Print, Return, Stmt, Var, While};
+use crate::expr::{Assign, Binary, Call, Conditional, Expr, Get, Grouping, Index, List, Literal, Logical, Map, Match, Unary, Variable, Set, SetIndex, This};
+use crate::pattern::{MatchArm, Pattern};
+use crate::stmt::{Block, Break, Class, Continue, Enum, Expression, For, ForIn, Function, If, Import, Print, Return, Stmt, Throw, Trait, Try, Var, While, Yield};
 use crate::token::Token;
-use crate::tokentype::{Literals, TokenType};
+use crate::tokentype::{FunctionKind, Literals, TokenType};
 use std::mem;
+use std::rc::Rc;
 
 pub struct Parser<'a> {
     tokens: &'a Vec<Token>,
@@ -11,90 +13,232 @@ pub struct Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &Vec<Token>) -> Parser {
+    pub fn new(tokens: &Vec<Token>) -> Parser<'_> {
         Parser { tokens, current: 0 }
     }
 
+    /// Parses the whole token stream. Syntax errors are reported as they are
+    /// found and the parser resynchronizes at the next statement, so one run
+    /// reports every error; the first message is returned if there were any.
     pub fn parse(&mut self) -> Result<Vec<Stmt>, String> {
         let mut statements: Vec<Stmt> = Vec::new();
+        let mut first_error = None;
         while !self.is_at_end() {
-            statements.push(self.declaration());
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(message) => {
+                    first_error.get_or_insert(message);
+                    self.synchronize();
+                }
+            }
+        }
+        match first_error {
+            Some(message) => Err(message),
+            None => Ok(statements),
         }
-        Ok(statements)
     }
 
-    fn declaration(&mut self) -> Stmt {
+    fn declaration(&mut self) -> Result<Stmt, String> {
         if self.match_token(vec![TokenType::CLASS]) {
             return self.class_declaration();
         }
+        if self.match_token(vec![TokenType::TRAIT]) {
+            return self.trait_declaration();
+        }
+        if self.match_token(vec![TokenType::ENUM]) {
+            return self.enum_declaration();
+        }
         if self.match_token(vec![TokenType::FUN]) {
             return self.function("function");
         }
         if self.match_token(vec![TokenType::VAR]) {
             return self.var_declaration();
         }
+        if self.match_token(vec![TokenType::CONST]) {
+            return self.const_declaration();
+        }
+        if self.match_token(vec![TokenType::IMPORT]) {
+            return self.import_declaration();
+        }
+        if self.match_token(vec![TokenType::FROM]) {
+            return self.selective_import_declaration();
+        }
         self.statement()
     }
 
-    fn class_declaration(&mut self) -> Stmt {
-        let name = self.consume(TokenType::IDENTIFIER, "Expect class name.");
-        self.consume(TokenType::LEFT_BRACE, "Expect '{' before class body.");
+    fn import_declaration(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let path = self.consume(TokenType::STRING, "Expect module path.")?;
+        self.consume(TokenType::AS, "Expect 'as' after module path.")?;
+        let name = self.consume(TokenType::IDENTIFIER, "Expect module name.")?;
+        self.consume(TokenType::SEMICOLON, "Expect ';' after import.")?;
+        Ok(Import::new(keyword, path, Some(name), Vec::new()))
+    }
+
+    fn selective_import_declaration(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let path = self.consume(TokenType::STRING, "Expect module path.")?;
+        self.consume(TokenType::IMPORT, "Expect 'import' after module path.")?;
+        let mut names = Vec::new();
+        while {
+            names.push(self.consume(TokenType::IDENTIFIER, "Expect name to import.")?);
+            self.match_token(vec![TokenType::COMMA])
+        } {}
+        self.consume(TokenType::SEMICOLON, "Expect ';' after import.")?;
+        Ok(Import::new(keyword, path, None, names))
+    }
+
+    /// `class Name with A, B { methods }`.
+    fn class_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::IDENTIFIER, "Expect class name.")?;
+        let mut traits = Vec::new();
+        if self.match_token(vec![TokenType::WITH]) {
+            loop {
+                let name = self.consume(TokenType::IDENTIFIER, "Expect trait name.")?;
+                traits.push(Variable::new(name));
+                if !self.match_token(vec![TokenType::COMMA]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::LEFT_BRACE, "Expect '{' before class body.")?;
+        let methods = self.methods("class")?;
+        Ok(Class::new(name, traits, methods))
+    }
+
+    fn trait_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::IDENTIFIER, "Expect trait name.")?;
+        self.consume(TokenType::LEFT_BRACE, "Expect '{' before trait body.")?;
+        let methods = self.methods("trait")?;
+        Ok(Trait::new(name, methods))
+    }
+
+    fn methods(&mut self, kind: &str) -> Result<Vec<Function>, String> {
         let mut methods: Vec<Function> = Vec::new();
         while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
-            if let Stmt::Function(f) = self.function("methods") {
-                methods.push(f);
-            } else {
-                unreachable!()
+            methods.push(self.method()?);
+        }
+        self.consume(
+            TokenType::RIGHT_BRACE,
+            format!("Expect '}}' after {} body.", kind).as_str(),
+        )?;
+        Ok(methods)
+    }
+
+    /// `enum Name { A, B, C }`; a trailing comma is allowed.
+    fn enum_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::IDENTIFIER, "Expect enum name.")?;
+        self.consume(TokenType::LEFT_BRACE, "Expect '{' before enum body.")?;
+        let mut variants = Vec::new();
+        while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
+            variants.push(self.consume(TokenType::IDENTIFIER, "Expect variant name.")?);
+            if !self.match_token(vec![TokenType::COMMA]) {
+                break;
             }
         }
-        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after class body.");
-        Class::new(name, methods)
+        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after enum variants.")?;
+        Ok(Enum::new(name, variants))
     }
 
-    fn function(&mut self, kind: &str) -> Stmt {
+    /// `class name() {}` is static, `name {}` a getter and `set name(v) {}` a setter.
+    fn method(&mut self) -> Result<Function, String> {
+        let mut kind = FunctionKind::METHOD;
+        if self.match_token(vec![TokenType::CLASS]) {
+            kind = FunctionKind::STATIC;
+        } else if self.peek().lexeme == "set" && self.check_next(TokenType::IDENTIFIER) {
+            self.advance();
+            kind = FunctionKind::SETTER;
+        }
+        let name = self.consume(TokenType::IDENTIFIER, "Expect methods name.")?;
+        if kind == FunctionKind::METHOD && self.check(TokenType::LEFT_BRACE) {
+            kind = FunctionKind::GETTER;
+        }
+        if let Stmt::Function(f) = self.function_rest(name, kind, "methods")? {
+            Ok(Rc::unwrap_or_clone(f))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn function(&mut self, kind: &str) -> Result<Stmt, String> {
         let name = self.consume(
             TokenType::IDENTIFIER,
             format!("Expect {} name.", kind).as_str(),
-        );
-        self.consume(
-            TokenType::LEFT_PAREN,
-            format!("Expect '(' after {} name.", kind).as_str(),
-        );
+        )?;
+        self.function_rest(name, FunctionKind::FUNCTION, kind)
+    }
+
+    fn function_rest(&mut self, name: Token, function_kind: FunctionKind, kind: &str) -> Result<Stmt, String> {
         let mut parameters: Vec<Token> = Vec::new();
-        if !self.check(TokenType::RIGHT_PAREN) {
-            while {
-                if parameters.len() >= 8 {
-                    self.error(self.peek(), "Cannot have more than 8 parameters.")
-                        .unwrap();
-                }
-                parameters.push(self.consume(TokenType::IDENTIFIER, "Expect parameter name."));
-                self.match_token(vec![TokenType::COMMA])
-            } {}
+        let mut defaults: Vec<Option<Expr>> = Vec::new();
+        let mut rest = None;
+        if function_kind != FunctionKind::GETTER {
+            self.consume(
+                TokenType::LEFT_PAREN,
+                format!("Expect '(' after {} name.", kind).as_str(),
+            )?;
+            if !self.check(TokenType::RIGHT_PAREN) {
+                while {
+                    if parameters.len() >= 255 {
+                        let _ = self.error(self.peek(), "Cannot have more than 255 parameters.");
+                    }
+                    if self.match_token(vec![TokenType::DOT_DOT_DOT]) {
+                        rest = Some(self.consume(TokenType::IDENTIFIER, "Expect parameter name.")?);
+                        false
+                    } else {
+                        parameters.push(self.consume(TokenType::IDENTIFIER, "Expect parameter name.")?);
+                        if self.match_token(vec![TokenType::EQUAL]) {
+                            defaults.push(Some(self.expression()?));
+                        } else {
+                            if defaults.iter().any(Option::is_some) {
+                                let _ = self.error(&self.previous(), "Expect default value after parameters with defaults.");
+                            }
+                            defaults.push(None);
+                        }
+                        self.match_token(vec![TokenType::COMMA])
+                    }
+                } {}
+            }
+            self.consume(TokenType::RIGHT_PAREN, "Expect ')' after parameters.")?;
+            if function_kind == FunctionKind::SETTER && (parameters.len() != 1 || rest.is_some()) {
+                let _ = self.error(&name, "Setter must take exactly one parameter.");
+            }
         }
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after parameters.");
         self.consume(
             TokenType::LEFT_BRACE,
             format!("Expect '{{' before {} body.", kind).as_str(),
-        );
-        let body = self.block();
-        Function::new(name, parameters, body)
+        )?;
+        let body = self.block()?;
+        Ok(Function::new(name, function_kind, parameters, defaults, rest, body))
     }
 
-    fn var_declaration(&mut self) -> Stmt {
-        let name = self.consume(TokenType::IDENTIFIER, "Expect variable name.");
+    fn var_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::IDENTIFIER, "Expect variable name.")?;
         let initializer = if self.match_token(vec![TokenType::EQUAL]) {
-            self.expression().unwrap()
+            Some(self.expression()?)
         } else {
-            Literal::new(Literals::NIL(None))
+            None
         };
         self.consume(
             TokenType::SEMICOLON,
             "Expect ';' after variable declaration.",
-        );
-        Var::new(name, initializer)
+        )?;
+        Ok(Var::new(name, initializer, false))
+    }
+
+    /// `const NAME = value;`, a variable that cannot be assigned to.
+    fn const_declaration(&mut self) -> Result<Stmt, String> {
+        let name = self.consume(TokenType::IDENTIFIER, "Expect constant name.")?;
+        self.consume(TokenType::EQUAL, "Expect '=' after constant name.")?;
+        let initializer = self.expression()?;
+        self.consume(
+            TokenType::SEMICOLON,
+            "Expect ';' after constant declaration.",
+        )?;
+        Ok(Var::new(name, Some(initializer), true))
     }
 
-    fn statement(&mut self) -> Stmt {
+    fn statement(&mut self) -> Result<Stmt, String> {
         if self.match_token(vec![TokenType::FOR]) {
             return self.for_statement();
         }
@@ -107,102 +251,167 @@ impl<'a> Parser<'a> {
         if self.match_token(vec![TokenType::RETURN]) {
             return self.return_statement();
         }
+        if self.match_token(vec![TokenType::YIELD]) {
+            return self.yield_statement();
+        }
         if self.match_token(vec![TokenType::WHILE]) {
             return self.while_statement();
         }
+        if self.match_token(vec![TokenType::THROW]) {
+            return self.throw_statement();
+        }
+        if self.match_token(vec![TokenType::TRY]) {
+            return self.try_statement();
+        }
+        if self.match_token(vec![TokenType::BREAK]) {
+            let keyword = self.previous();
+            self.consume(TokenType::SEMICOLON, "Expect ';' after 'break'.")?;
+            return Ok(Break::new(keyword));
+        }
+        if self.match_token(vec![TokenType::CONTINUE]) {
+            let keyword = self.previous();
+            self.consume(TokenType::SEMICOLON, "Expect ';' after 'continue'.")?;
+            return Ok(Continue::new(keyword));
+        }
         if self.match_token(vec![TokenType::LEFT_BRACE]) {
-            return Block::new(self.block());
+            return Ok(Block::new(self.block()?));
         }
         self.expression_statement()
     }
 
-    fn for_statement(&mut self) -> Stmt {
+    fn for_statement(&mut self) -> Result<Stmt, String> {
         let token = self.previous();
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'for'.");
+        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'for'.")?;
+        if self.check(TokenType::VAR)
+            && self.check_next(TokenType::IDENTIFIER)
+            && matches!(self.tokens.get(self.current + 2).map(|t| t.token_type), Some(TokenType::IN))
+        {
+            self.advance();
+            let name = self.advance();
+            self.advance();
+            let iterable = self.expression()?;
+            self.consume(TokenType::RIGHT_PAREN, "Expect ')' after for-in clause.")?;
+            let body = self.statement()?;
+            return Ok(ForIn::new(token, name, iterable, body));
+        }
         let initializer = if self.match_token(vec![TokenType::SEMICOLON]) {
             None
         } else if self.match_token(vec![TokenType::VAR]) {
-            Some(self.var_declaration())
+            Some(self.var_declaration()?)
         } else {
-            Some(self.expression_statement())
+            Some(self.expression_statement()?)
         };
         let condition = if !self.check(TokenType::SEMICOLON) {
-            self.expression().unwrap()
+            Some(self.expression()?)
         } else {
-            Literal::new(Literals::BOOL(true))
+            None
         };
-        self.consume(TokenType::SEMICOLON, "Expect ';' after loop condition.");
+        self.consume(TokenType::SEMICOLON, "Expect ';' after loop condition.")?;
         let increment = if !self.check(TokenType::RIGHT_PAREN) {
-            Some(self.expression().unwrap())
+            Some(self.expression()?)
         } else {
             None
         };
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after for clauses.");
-        let mut body = self.statement();
-
-        if let Some(i) = increment {
-            body = Block::new(vec![body, Expression::new(i)]);
-        }
-        body = While::new(token, condition, body);
-        if let Some(i) = initializer {
-            body = Block::new(vec![i, body]);
-        }
-        body
+        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after for clauses.")?;
+        let body = self.statement()?;
+        Ok(For::new(token, initializer, condition, increment, body))
     }
 
-    fn if_statement(&mut self) -> Stmt {
+    fn if_statement(&mut self) -> Result<Stmt, String> {
         let token = self.previous();
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'if'.");
-        let condition = self.expression().unwrap();
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after if condition.");
-        let then_branch = self.statement();
+        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after if condition.")?;
+        let then_branch = self.statement()?;
         let else_branch = if self.match_token(vec![TokenType::ELSE]) {
-            Some(self.statement())
+            Some(self.statement()?)
         } else {
             None
         };
-        If::new(token, condition, then_branch, else_branch)
+        Ok(If::new(token, condition, then_branch, else_branch))
     }
 
-    fn print_statement(&mut self) -> Stmt {
-        let expr = self.expression().unwrap();
-        self.consume(TokenType::SEMICOLON, "Expect ';' after value.");
-        Print::new(expr)
+    fn print_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let expr = self.expression()?;
+        self.consume(TokenType::SEMICOLON, "Expect ';' after value.")?;
+        Ok(Print::new(keyword, expr))
     }
 
-    fn return_statement(&mut self) -> Stmt {
+    fn return_statement(&mut self) -> Result<Stmt, String> {
         let keyword = self.previous();
         let value = if !self.check(TokenType::SEMICOLON) {
-            self.expression().unwrap()
+            Some(self.expression()?)
         } else {
-            Literal::new(Literals::NIL(None))
+            None
         };
-        self.consume(TokenType::SEMICOLON, "Expect ';' after return value.");
-        Return::new(keyword, value)
+        self.consume(TokenType::SEMICOLON, "Expect ';' after return value.")?;
+        Ok(Return::new(keyword, value))
     }
 
-    fn while_statement(&mut self) -> Stmt {
+    fn yield_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenType::SEMICOLON, "Expect ';' after yielded value.")?;
+        Ok(Yield::new(keyword, value))
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt, String> {
         let token = self.previous();
-        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'while'.");
-        let condition = self.expression().unwrap();
-        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after 'condition'.");
-        let body = self.statement();
-        While::new(token, condition, body)
+        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after 'condition'.")?;
+        let body = self.statement()?;
+        Ok(While::new(token, condition, body))
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenType::SEMICOLON, "Expect ';' after thrown value.")?;
+        Ok(Throw::new(keyword, value))
+    }
+
+    fn try_statement(&mut self) -> Result<Stmt, String> {
+        let keyword = self.previous();
+        self.consume(TokenType::LEFT_BRACE, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+        let mut name = None;
+        let mut catch_body = Vec::new();
+        if self.match_token(vec![TokenType::CATCH]) {
+            self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'catch'.")?;
+            name = Some(self.consume(TokenType::IDENTIFIER, "Expect exception variable name.")?);
+            self.consume(TokenType::RIGHT_PAREN, "Expect ')' after exception variable.")?;
+            self.consume(TokenType::LEFT_BRACE, "Expect '{' before catch body.")?;
+            catch_body = self.block()?;
+        }
+        let finally_body = if self.match_token(vec![TokenType::FINALLY]) {
+            self.consume(TokenType::LEFT_BRACE, "Expect '{' after 'finally'.")?;
+            Some(self.block()?)
+        } else {
+            None
+        };
+        if name.is_none() && finally_body.is_none() {
+            return Err(self
+                .error(self.peek(), "Expect 'catch' or 'finally' after try block.")
+                .unwrap_err());
+        }
+        Ok(Try::new(keyword, body, name, catch_body, finally_body))
     }
 
-    fn expression_statement(&mut self) -> Stmt {
-        let expr = self.expression().unwrap();
-        self.consume(TokenType::SEMICOLON, "Expect ';' after value.");
-        Expression::new(expr)
+    fn expression_statement(&mut self) -> Result<Stmt, String> {
+        let expr = self.expression()?;
+        self.consume(TokenType::SEMICOLON, "Expect ';' after value.")?;
+        Ok(Expression::new(expr))
     }
 
-    fn block(&mut self) -> Vec<Stmt> {
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
         let mut statements: Vec<Stmt> = Vec::new();
         while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
-            statements.push(self.declaration());
+            statements.push(self.declaration()?);
         }
-        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after block.");
-        statements
+        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after block.")?;
+        Ok(statements)
     }
 
     fn expression(&mut self) -> Result<Expr, String> {
@@ -211,7 +420,7 @@ impl<'a> Parser<'a> {
     }
 
     fn assignment(&mut self) -> Result<Expr, String> {
-        let expr = self.or()?;
+        let expr = self.conditional()?;
         // let expr = self.equality()?;
         if self.match_token(vec![TokenType::EQUAL]) {
             let equals = self.previous();
@@ -221,9 +430,12 @@ impl<'a> Parser<'a> {
                     let name = e.name;
                     return Ok(Assign::new(name, value));
                 }
-                Expr::Get(e) => {
+                Expr::Get(e) if !e.optional => {
                     return Ok(Set::new(*e.object, e.name, value))
                 }
+                Expr::Index(e) => {
+                    return Ok(SetIndex::new(*e.object, e.bracket, *e.index, value))
+                }
                 _ => {
                     return Err(self
                         .error(&equals, "Invalid assignment target.")
@@ -234,6 +446,28 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    fn conditional(&mut self) -> Result<Expr, String> {
+        let expr = self.coalesce()?;
+        if self.match_token(vec![TokenType::QUESTION]) {
+            let question = self.previous();
+            let then_branch = self.expression()?;
+            self.consume(TokenType::COLON, "Expect ':' after then branch of conditional expression.")?;
+            let else_branch = self.conditional()?;
+            return Ok(Conditional::new(expr, question, then_branch, else_branch));
+        }
+        Ok(expr)
+    }
+
+    fn coalesce(&mut self) -> Result<Expr, String> {
+        let mut expr = self.or()?;
+        while self.match_token(vec![TokenType::QUESTION_QUESTION]) {
+            let operator = self.previous();
+            let right = self.or()?;
+            expr = Logical::new(expr, operator, right);
+        }
+        Ok(expr)
+    }
+
     fn or(&mut self) -> Result<Expr, String> {
         let mut expr = self.and()?;
         while self.match_token(vec![TokenType::OR]) {
@@ -314,8 +548,16 @@ impl<'a> Parser<'a> {
             if self.match_token(vec![TokenType::LEFT_PAREN]) {
                 expr = self.finish_call(expr)?;
             } else if self.match_token(vec![TokenType::DOT]) {
-                let name = self.consume(TokenType::IDENTIFIER, "Expect property name after '.'.");
-                expr = Get::new(expr, name);
+                let name = self.consume(TokenType::IDENTIFIER, "Expect property name after '.'.")?;
+                expr = Get::new(expr, name, false);
+            } else if self.match_token(vec![TokenType::LEFT_BRACKET]) {
+                let bracket = self.previous();
+                let index = self.expression()?;
+                self.consume(TokenType::RIGHT_BRACKET, "Expect ']' after index.")?;
+                expr = Index::new(expr, bracket, index);
+            } else if self.match_token(vec![TokenType::QUESTION_DOT]) {
+                let name = self.consume(TokenType::IDENTIFIER, "Expect property name after '?.'.")?;
+                expr = Get::new(expr, name, true);
             } else {
                 break;
             }
@@ -323,19 +565,27 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    /// Named arguments (`name: value`) come after the positional ones.
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, String> {
         let mut arguments: Vec<Expr> = Vec::new();
+        let mut names: Vec<Token> = Vec::new();
         if !self.check(TokenType::RIGHT_PAREN) {
             while {
-                if arguments.len() >= 8 {
-                    self.error(self.peek(), "Cannot have more than 8 arguments.")?;
+                if arguments.len() >= 255 {
+                    let _ = self.error(self.peek(), "Cannot have more than 255 arguments.");
+                }
+                if self.check(TokenType::IDENTIFIER) && self.check_next(TokenType::COLON) {
+                    names.push(self.advance());
+                    self.advance();
+                } else if !names.is_empty() {
+                    self.error(self.peek(), "Expect named argument after named arguments.")?;
                 }
                 arguments.push(self.expression()?);
                 self.match_token(vec![TokenType::COMMA])
             } {}
         }
-        let paren = self.consume(TokenType::RIGHT_PAREN, "Expect ')' after arguments.");
-        Ok(Call::new(callee, paren, arguments))
+        let paren = self.consume(TokenType::RIGHT_PAREN, "Expect ')' after arguments.")?;
+        Ok(Call::new(callee, paren, arguments, names))
     }
 
     fn primary(&mut self) -> Result<Expr, String> {
@@ -354,12 +604,21 @@ impl<'a> Parser<'a> {
         }
         if self.match_token(vec![TokenType::LEFT_PAREN]) {
             let expr = self.expression()?;
-            self.consume(TokenType::RIGHT_PAREN, "Expect ')' after expression.");
+            self.consume(TokenType::RIGHT_PAREN, "Expect ')' after expression.")?;
             return Ok(Grouping::new(expr));
         }
         if self.match_token(vec![TokenType::THIS]) {
             return Ok(This::new(self.previous()))
         }
+        if self.match_token(vec![TokenType::LEFT_BRACKET]) {
+            return self.list();
+        }
+        if self.match_token(vec![TokenType::LEFT_BRACE]) {
+            return self.map();
+        }
+        if self.match_token(vec![TokenType::MATCH]) {
+            return self.match_expression();
+        }
         if self.match_token(vec![TokenType::IDENTIFIER]) {
             let name = self.previous();
             return Ok(Variable::new(name));
@@ -367,6 +626,110 @@ impl<'a> Parser<'a> {
         Err(self.error(self.peek(), "Expect expression.").unwrap_err())
     }
 
+    fn list(&mut self) -> Result<Expr, String> {
+        let bracket = self.previous();
+        let mut elements = Vec::new();
+        if !self.check(TokenType::RIGHT_BRACKET) {
+            while {
+                elements.push(self.expression()?);
+                self.match_token(vec![TokenType::COMMA])
+            } {}
+        }
+        self.consume(TokenType::RIGHT_BRACKET, "Expect ']' after list elements.")?;
+        Ok(List::new(bracket, elements))
+    }
+
+    /// `{key: value, ...}`; only parsed where an expression is expected, so a
+    /// `{` starting a statement is still a block.
+    fn map(&mut self) -> Result<Expr, String> {
+        let brace = self.previous();
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+        if !self.check(TokenType::RIGHT_BRACE) {
+            while {
+                keys.push(self.expression()?);
+                self.consume(TokenType::COLON, "Expect ':' after map key.")?;
+                values.push(self.expression()?);
+                self.match_token(vec![TokenType::COMMA])
+            } {}
+        }
+        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after map entries.")?;
+        Ok(Map::new(brace, keys, values))
+    }
+
+    /// `match (value) { pattern => expr, ... }`; a trailing comma is allowed.
+    fn match_expression(&mut self) -> Result<Expr, String> {
+        let keyword = self.previous();
+        self.consume(TokenType::LEFT_PAREN, "Expect '(' after 'match'.")?;
+        let value = self.expression()?;
+        self.consume(TokenType::RIGHT_PAREN, "Expect ')' after match value.")?;
+        self.consume(TokenType::LEFT_BRACE, "Expect '{' before match arms.")?;
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RIGHT_BRACE) && !self.is_at_end() {
+            let token = self.peek().clone();
+            let pattern = self.pattern()?;
+            self.consume(TokenType::ARROW, "Expect '=>' after pattern.")?;
+            let body = self.expression()?;
+            arms.push(MatchArm { token, pattern, body });
+            if !self.match_token(vec![TokenType::COMMA]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RIGHT_BRACE, "Expect '}' after match arms.")?;
+        Ok(Match::new(keyword, value, arms))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, String> {
+        let pattern = self.primary_pattern()?;
+        if !self.check(TokenType::PIPE) {
+            return Ok(pattern);
+        }
+        let mut alternatives = vec![pattern];
+        while self.match_token(vec![TokenType::PIPE]) {
+            alternatives.push(self.primary_pattern()?);
+        }
+        Ok(Pattern::ALTERNATIVE(alternatives))
+    }
+
+    fn primary_pattern(&mut self) -> Result<Pattern, String> {
+        if matches!(
+            self.peek().token_type,
+            TokenType::FALSE | TokenType::TRUE | TokenType::NIL | TokenType::NUMBER | TokenType::STRING
+        ) {
+            return Ok(Pattern::LITERAL(self.primary()?));
+        }
+        if self.match_token(vec![TokenType::MINUS]) {
+            let operator = self.previous();
+            let number = self.consume(TokenType::NUMBER, "Expect number after '-' in pattern.")?;
+            return Ok(Pattern::LITERAL(Unary::new(operator, Literal::new(number.literal.unwrap()))));
+        }
+        if self.match_token(vec![TokenType::IDENTIFIER]) {
+            let name = self.previous();
+            if name.lexeme == "_" {
+                return Ok(Pattern::WILDCARD(name));
+            }
+            if !self.match_token(vec![TokenType::LEFT_BRACE]) {
+                return Ok(Pattern::BINDING(name));
+            }
+            let mut fields = Vec::new();
+            if !self.check(TokenType::RIGHT_BRACE) {
+                while {
+                    let field = self.consume(TokenType::IDENTIFIER, "Expect field name in pattern.")?;
+                    let pattern = if self.match_token(vec![TokenType::COLON]) {
+                        self.pattern()?
+                    } else {
+                        Pattern::BINDING(field.clone())
+                    };
+                    fields.push((field, pattern));
+                    self.match_token(vec![TokenType::COMMA])
+                } {}
+            }
+            self.consume(TokenType::RIGHT_BRACE, "Expect '}' after pattern fields.")?;
+            return Ok(Pattern::INSTANCE(Variable::new(name), fields));
+        }
+        Err(self.error(self.peek(), "Expect pattern.").unwrap_err())
+    }
+
     fn match_token(&mut self, types: Vec<TokenType>) -> bool {
         for t in types {
             if self.check(t) {
@@ -385,6 +748,14 @@ impl<'a> Parser<'a> {
         mem::discriminant(&token_type) == mem::discriminant(&token.token_type)
     }
 
+    /// Like `check`, for the token after the current one.
+    fn check_next(&self, token_type: TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => mem::discriminant(&token_type) == mem::discriminant(&token.token_type),
+            None => false,
+        }
+    }
+
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -393,10 +764,7 @@ impl<'a> Parser<'a> {
     }
 
     fn is_at_end(&self) -> bool {
-        match self.peek().token_type {
-            TokenType::EOF => true,
-            _ => false,
-        }
+        matches!(self.peek().token_type, TokenType::EOF)
     }
 
     fn peek(&self) -> &Token {
@@ -407,12 +775,11 @@ impl<'a> Parser<'a> {
         self.tokens[self.current - 1].clone()
     }
 
-    fn consume(&mut self, token_type: TokenType, message: &str) -> Token {
+    fn consume(&mut self, token_type: TokenType, message: &str) -> Result<Token, String> {
         if self.check(token_type) {
-            return self.advance();
+            return Ok(self.advance());
         }
-        self.error(self.peek(), message).unwrap();
-        panic!();
+        Err(self.error(self.peek(), message).unwrap_err())
     }
 
     fn error(&self, token: &Token, message: &str) -> Result<(), String> {
@@ -429,18 +796,26 @@ impl<'a> Parser<'a> {
             }
             match self.peek().token_type {
                 TokenType::CLASS
+                | TokenType::TRAIT
+                | TokenType::ENUM
                 | TokenType::FUN
                 | TokenType::VAR
+                | TokenType::CONST
                 | TokenType::FOR
                 | TokenType::IF
                 | TokenType::WHILE
                 | TokenType::PRINT
-                | TokenType::RETURN => {
+                | TokenType::RETURN
+                | TokenType::YIELD
+                | TokenType::THROW
+                | TokenType::TRY
+                | TokenType::IMPORT
+                | TokenType::FROM => {
                     return;
                 }
                 _ => {}
             }
+            self.advance();
         }
-        self.advance();
     }
 }
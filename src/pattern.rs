@@ -0,0 +1,48 @@
+use crate::expr::Expr;
+use crate::token::Token;
+
+/// The left-hand side of a `match` arm.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// `_`, which matches anything.
+    WILDCARD(Token),
+    /// A literal, or a negated number literal, compared without raising
+    /// errors for mismatched types.
+    LITERAL(Expr),
+    /// A name bound to the matched value in the arm.
+    BINDING(Token),
+    /// `a | b`; alternatives may not bind names.
+    ALTERNATIVE(Vec<Pattern>),
+    /// `Class{field, field: pattern}` matches instances of the class whose
+    /// fields match; a bare field name binds the field's value to that name.
+    INSTANCE(Expr, Vec<(Token, Pattern)>),
+}
+
+impl Pattern {
+    /// Whether the pattern matches every value, making later arms unreachable.
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::WILDCARD(_) | Pattern::BINDING(_) => true,
+            Pattern::ALTERNATIVE(alternatives) => alternatives.iter().any(Pattern::is_irrefutable),
+            Pattern::LITERAL(_) | Pattern::INSTANCE(..) => false,
+        }
+    }
+
+    /// The first name the pattern binds, if any.
+    pub fn first_binding(&self) -> Option<&Token> {
+        match self {
+            Pattern::BINDING(name) => Some(name),
+            Pattern::ALTERNATIVE(alternatives) => alternatives.iter().find_map(Pattern::first_binding),
+            Pattern::INSTANCE(_, fields) => fields.iter().find_map(|(_, pattern)| pattern.first_binding()),
+            Pattern::WILDCARD(_) | Pattern::LITERAL(_) => None,
+        }
+    }
+}
+
+/// `pattern => body`; `token` is the pattern's first token.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub token: Token,
+    pub pattern: Pattern,
+    pub body: Expr,
+}
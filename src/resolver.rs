@@ -1,12 +1,15 @@
-use crate::error::parse_error;
+use crate::error::{parse_error, warning};
 use crate::expr;
-use crate::expr::{Assign, Binary, Call, Expr, Get, Grouping, Literal, Logical, Unary, Variable, Set, This};
+use crate::expr::{Assign, Binary, Call, Conditional, Expr, Get, Grouping, Index, List, Literal, Logical, Map, Match, Unary, Variable, Set, SetIndex, This};
 use crate::interpreter::Interpreter;
+use crate::lox_generator::contains_yield;
+use crate::pattern::Pattern;
 use crate::stmt;
-use crate::stmt::{Block, Class, Expression, Function, If, Print, Return, Stmt, Var, While};
+use crate::stmt::{Block, Break, Class, Continue, Enum, Expression, For, ForIn, Function, If, Import, Print, Return, Stmt, Throw, Trait, Try, Var, While, Yield};
 use crate::token::Token;
-use crate::tokentype::Literals;
+use crate::tokentype::{FunctionKind, Literals};
 use std::collections::HashMap;
+use std::mem;
 
 #[derive(Debug, Copy, Clone)]
 enum FunctionType {
@@ -14,6 +17,8 @@ enum FunctionType {
     FUNCTION,
     METHOD,
     INITIALIZER,
+    /// A function or method whose body yields.
+    GENERATOR,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -22,25 +27,39 @@ enum ClassType {
     CLASS,
 }
 
+#[derive(Debug, Copy, Clone)]
+struct Local {
+    defined: bool,
+    slot: usize,
+    constant: bool,
+}
+
 pub struct Resolver<'a> {
     interpreter: &'a mut Interpreter,
-    scopes: Vec<HashMap<String, bool>>,
+    scopes: Vec<HashMap<String, Local>>,
     current_func: FunctionType,
     current_class: ClassType,
+    /// Loops enclosing the current statement within its function.
+    loop_depth: usize,
+    /// Members of the traits declared in the globals and each of `scopes`,
+    /// to find conflicts between them.
+    traits: Vec<HashMap<String, Vec<(FunctionKind, String)>>>,
 }
 
 impl<'a> Resolver<'a> {
-    pub fn new(interpreter: &mut Interpreter) -> Resolver {
+    pub fn new(interpreter: &mut Interpreter) -> Resolver<'_> {
         Resolver {
             interpreter,
             scopes: Vec::new(),
             current_func: FunctionType::NONE,
             current_class: ClassType::NONE,
+            loop_depth: 0,
+            traits: vec![HashMap::new()],
         }
     }
     pub fn resolves(&mut self, statements: &Vec<Stmt>) {
         for statement in statements {
-            self.resolve_s(&statement);
+            self.resolve_s(statement);
         }
     }
 
@@ -54,13 +73,16 @@ impl<'a> Resolver<'a> {
 
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.traits.push(HashMap::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop();
+        self.traits.pop();
     }
 
     fn declare(&mut self, name: &Token) {
+        self.traits.last_mut().unwrap().remove(&name.lexeme);
         if !self.scopes.is_empty() {
             let len = self.scopes.len();
             let scope = self.scopes.get_mut(len - 1).unwrap();
@@ -69,62 +91,172 @@ impl<'a> Resolver<'a> {
                     name,
                     "Variable with this name already declared in this scope.",
                 );
-                panic!();
+                return;
             }
-            scope.insert(name.lexeme.clone(), false);
+            let slot = scope.len();
+            scope.insert(name.lexeme.clone(), Local { defined: false, slot, constant: false });
+            self.interpreter.resolve_declaration(name, slot);
         }
     }
 
+    /// Members of the trait `name` refers to, if it resolves to one declared
+    /// in this source; anything else is left to the runtime check.
+    fn trait_members(&self, name: &Token) -> Option<&Vec<(FunctionKind, String)>> {
+        let depth = self.scopes.iter().rposition(|scope| scope.contains_key(&name.lexeme)).map_or(0, |i| i + 1);
+        self.traits[depth].get(&name.lexeme)
+    }
+
     fn define(&mut self, name: &Token) {
-        if !self.scopes.is_empty() {
-            let len = self.scopes.len();
-            let scope = self.scopes.get_mut(len - 1).unwrap();
-            scope.insert(name.lexeme.clone(), true);
+        if let Some(scope) = self.scopes.last_mut() {
+            if let Some(local) = scope.get_mut(&name.lexeme) {
+                local.defined = true;
+            }
+        }
+    }
+
+    fn define_constant(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if let Some(local) = scope.get_mut(&name.lexeme) {
+                local.defined = true;
+                local.constant = true;
+            }
         }
     }
 
-    fn resolve_local(&mut self, name: &Token) {
+    /// Whether `name` resolves to a local constant; globals are checked when
+    /// they are assigned.
+    fn is_constant(&self, name: &Token) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme))
+            .is_some_and(|local| local.constant)
+    }
+
+    fn resolve_local(&mut self, expr_id: usize, name: &Token) {
         for (i, item) in self.scopes.iter().rev().enumerate() {
-            if item.contains_key(&name.lexeme) {
-                self.interpreter.resolve(name.id, i);
+            if let Some(local) = item.get(&name.lexeme) {
+                self.interpreter.resolve(expr_id, i, local.slot);
                 return;
             }
         }
     }
 
+    /// Resolves the literals and classes a pattern tests against, which the
+    /// interpreter evaluates before the arm's bindings exist.
+    fn resolve_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::WILDCARD(_) | Pattern::BINDING(_) => {}
+            Pattern::LITERAL(literal) => self.resolve_e(literal),
+            Pattern::ALTERNATIVE(alternatives) => {
+                for alternative in alternatives {
+                    if let Some(name) = alternative.first_binding() {
+                        parse_error(name, "Cannot bind names in alternative patterns.");
+                    } else {
+                        self.resolve_pattern(alternative);
+                    }
+                }
+            }
+            Pattern::INSTANCE(class, fields) => {
+                self.resolve_e(class);
+                for (_, field) in fields {
+                    self.resolve_pattern(field);
+                }
+            }
+        }
+    }
+
+    /// Declares the pattern's bindings depth-first, the order the interpreter
+    /// defines them in.
+    fn declare_bindings(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::BINDING(name) => {
+                self.declare(name);
+                self.define(name);
+            }
+            Pattern::INSTANCE(_, fields) => {
+                for (_, field) in fields {
+                    self.declare_bindings(field);
+                }
+            }
+            // Alternatives may not bind names.
+            Pattern::WILDCARD(_) | Pattern::LITERAL(_) | Pattern::ALTERNATIVE(_) => {}
+        }
+    }
+
+    /// Resolves the members of a class or trait, which all see `this`.
+    fn resolve_methods(&mut self, methods: &[Function]) {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::CLASS;
+        self.begin_scope();
+        let last = self.scopes.len() - 1;
+        self.scopes[last].insert("this".to_string(), Local { defined: true, slot: 0, constant: false });
+
+        for method in methods.iter() {
+            // Static methods are bound to the class itself, so they share `this`.
+            if method.kind == FunctionKind::METHOD && method.name.lexeme == "init" {
+                self.resolve_function(method, FunctionType::INITIALIZER);
+            } else {
+                self.resolve_function(method, FunctionType::METHOD);
+            }
+        }
+        self.end_scope();
+        self.current_class = enclosing_class;
+    }
     fn resolve_function(&mut self, function: &Function, f_type: FunctionType) {
         let enclosing_func = self.current_func;
-        self.current_func = f_type;
+        let enclosing_loops = mem::replace(&mut self.loop_depth, 0);
+        self.current_func = match f_type {
+            FunctionType::FUNCTION | FunctionType::METHOD if contains_yield(&function.body) => FunctionType::GENERATOR,
+            f_type => f_type,
+        };
         self.begin_scope();
-        for param in function.params.iter() {
-            self.declare(&param);
-            self.define(&param);
+        // Defaults are evaluated in the call's scope, after the parameters before them.
+        for (param, default) in function.params.iter().zip(function.defaults.iter()) {
+            if let Some(default) = default {
+                self.resolve_e(default);
+            }
+            self.declare(param);
+            self.define(param);
+        }
+        for param in function.rest.iter() {
+            self.declare(param);
+            self.define(param);
         }
         self.resolves(&function.body);
         self.end_scope();
         self.current_func = enclosing_func;
+        self.loop_depth = enclosing_loops;
+    }
+
+    fn resolve_loop_body(&mut self, body: &Stmt) {
+        self.loop_depth += 1;
+        self.resolve_s(body);
+        self.loop_depth -= 1;
+    }
+
+    fn resolve_loop_jump(&mut self, keyword: &Token) {
+        if self.loop_depth == 0 {
+            parse_error(keyword, &format!("Cannot use '{}' outside of a loop.", keyword.lexeme));
+        }
     }
 }
 
 impl<'a> expr::Visitor<()> for Resolver<'a> {
     fn visit_variable_expr(&mut self, expr: &Variable) {
         // println!("{:?} {:?}", self.scopes, expr);
-        if !self.scopes.is_empty()
-            && !*self
-                .scopes
-                .last()
-                .unwrap()
-                .get(&expr.name.lexeme)
-                .or(Some(&true))
-                .unwrap()
+        if self
+            .scopes
+            .last()
+            .and_then(|scope| scope.get(&expr.name.lexeme))
+            .is_some_and(|local| !local.defined)
         {
             parse_error(
                 &expr.name,
                 "Cannot read local variable in its own initializer.",
             );
-            panic!();
         }
-        self.resolve_local(&expr.name);
+        self.resolve_local(expr.id, &expr.name);
     }
     fn visit_binary_expr(&mut self, expr: &Binary) {
         self.resolve_e(&expr.left);
@@ -133,13 +265,13 @@ impl<'a> expr::Visitor<()> for Resolver<'a> {
     fn visit_call_expr(&mut self, expr: &Call) {
         self.resolve_e(&expr.callee);
         for argument in expr.arguments.iter() {
-            self.resolve_e(&argument);
+            self.resolve_e(argument);
         }
     }
     fn visit_grouping_expr(&mut self, expr: &Grouping) {
         self.resolve_e(&expr.expression);
     }
-    fn visit_literal_expr(&self, _expr: &Literal) {}
+    fn visit_literal_expr(&mut self, _expr: &Literal) {}
     fn visit_logical_expr(&mut self, expr: &Logical) {
         self.resolve_e(&expr.left);
         self.resolve_e(&expr.right);
@@ -148,8 +280,11 @@ impl<'a> expr::Visitor<()> for Resolver<'a> {
         self.resolve_e(&expr.right);
     }
     fn visit_assign_expr(&mut self, expr: &Assign) {
+        if self.is_constant(&expr.name) {
+            parse_error(&expr.name, &format!("Cannot assign to constant '{}'.", expr.name.lexeme));
+        }
         self.resolve_e(&expr.value);
-        self.resolve_local(&expr.name);
+        self.resolve_local(expr.id, &expr.name);
     }
     fn visit_get_expr(&mut self, expr: &Get) {
         self.resolve_e(&expr.object);
@@ -160,9 +295,51 @@ impl<'a> expr::Visitor<()> for Resolver<'a> {
     }
     fn visit_this_expr(&mut self, expr: &This) {
         if let ClassType::NONE = self.current_class  {
-            panic!("Cannot use 'this' outside of a class.");
+            parse_error(&expr.keyword, "Cannot use 'this' outside of a class.");
+            return;
+        }
+        self.resolve_local(expr.id, &expr.keyword);
+    }
+    fn visit_conditional_expr(&mut self, expr: &Conditional) {
+        self.resolve_e(&expr.condition);
+        self.resolve_e(&expr.then_branch);
+        self.resolve_e(&expr.else_branch);
+    }
+    fn visit_list_expr(&mut self, expr: &List) {
+        for element in expr.elements.iter() {
+            self.resolve_e(element);
+        }
+    }
+    fn visit_map_expr(&mut self, expr: &Map) {
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            self.resolve_e(key);
+            self.resolve_e(value);
+        }
+    }
+    fn visit_index_expr(&mut self, expr: &Index) {
+        self.resolve_e(&expr.object);
+        self.resolve_e(&expr.index);
+    }
+    fn visit_set_index_expr(&mut self, expr: &SetIndex) {
+        self.resolve_e(&expr.value);
+        self.resolve_e(&expr.object);
+        self.resolve_e(&expr.index);
+    }
+    /// Each arm gets its own scope holding its bindings.
+    fn visit_match_expr(&mut self, expr: &Match) {
+        self.resolve_e(&expr.value);
+        let mut exhausted = false;
+        for arm in expr.arms.iter() {
+            if exhausted {
+                warning(&arm.token, "Unreachable match arm.");
+            }
+            exhausted |= arm.pattern.is_irrefutable();
+            self.resolve_pattern(&arm.pattern);
+            self.begin_scope();
+            self.declare_bindings(&arm.pattern);
+            self.resolve_e(&arm.body);
+            self.end_scope();
         }
-        self.resolve_local(&expr.keyword);
     }
 }
 
@@ -174,8 +351,14 @@ impl<'a> stmt::Visitor<()> for Resolver<'a> {
     }
     fn visit_var_stmt(&mut self, stmt: &Var) {
         self.declare(&stmt.name);
-        self.resolve_e(&stmt.initializer);
-        self.define(&stmt.name);
+        if let Some(initializer) = &stmt.initializer {
+            self.resolve_e(initializer);
+        }
+        if stmt.constant {
+            self.define_constant(&stmt.name);
+        } else {
+            self.define(&stmt.name);
+        }
     }
     fn visit_function_stmt(&mut self, stmt: &Function) {
         self.declare(&stmt.name);
@@ -188,8 +371,8 @@ impl<'a> stmt::Visitor<()> for Resolver<'a> {
     fn visit_if_stmt(&mut self, stmt: &If) {
         self.resolve_e(&stmt.condition);
         self.resolve_s(&stmt.then_branch);
-        if stmt.else_branch.is_some() {
-            self.resolve_s(&stmt.else_branch.as_ref().unwrap());
+        if let Some(else_branch) = &stmt.else_branch {
+            self.resolve_s(else_branch);
         }
     }
     fn visit_print_stmt(&mut self, stmt: &Print) {
@@ -197,43 +380,153 @@ impl<'a> stmt::Visitor<()> for Resolver<'a> {
     }
     fn visit_return_stmt(&mut self, stmt: &Return) {
         match self.current_func {
-            FunctionType::FUNCTION | FunctionType::METHOD => self.resolve_e(&stmt.value),
+            FunctionType::FUNCTION | FunctionType::METHOD => {
+                if let Some(value) = &stmt.value {
+                    self.resolve_e(value);
+                }
+            }
             FunctionType::INITIALIZER => {
-                if let Expr::Literal(l) = stmt.value.as_ref() {
-                    if let Literals::NIL(_) = l.value {
-                        self.resolve_e(&stmt.value);
-                        return;
-                    }
+                match stmt.value.as_deref() {
+                    None => return,
+                    Some(Expr::Literal(l)) if matches!(l.value, Literals::NIL(_)) => return,
+                    _ => {}
+                }
+                parse_error(&stmt.keyword, "Can not return a value from an initializer.");
+            }
+            FunctionType::GENERATOR => {
+                if let Some(value) = &stmt.value {
+                    parse_error(&stmt.keyword, "Cannot return a value from a generator.");
+                    self.resolve_e(value);
                 }
-                panic!("Can not return a value from an initializer.")
             }
             FunctionType::NONE => {
                 parse_error(&stmt.keyword, "Cannot return from top-level code.");
-                panic!();
             }
         }
     }
+    fn visit_yield_stmt(&mut self, stmt: &Yield) {
+        match self.current_func {
+            FunctionType::INITIALIZER => parse_error(&stmt.keyword, "Cannot yield from an initializer."),
+            FunctionType::NONE => parse_error(&stmt.keyword, "Cannot yield from top-level code."),
+            _ => {}
+        }
+        self.resolve_e(&stmt.value);
+    }
     fn visit_while_stmt(&mut self, stmt: &While) {
         self.resolve_e(&stmt.condition);
-        self.resolve_s(&stmt.body);
+        self.resolve_loop_body(&stmt.body);
     }
-    fn visit_class_stmt(&mut self, stmt: &Class) {
-        let enclosing_class = self.current_class;
-        self.current_class = ClassType::CLASS;
+    fn visit_for_stmt(&mut self, stmt: &For) {
+        self.begin_scope();
+        if let Some(initializer) = &stmt.initializer {
+            self.resolve_s(initializer);
+        }
+        if let Some(condition) = &stmt.condition {
+            self.resolve_e(condition);
+        }
+        if let Some(increment) = &stmt.increment {
+            self.resolve_e(increment);
+        }
+        self.resolve_loop_body(&stmt.body);
+        self.end_scope();
+    }
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) {
+        self.resolve_e(&stmt.iterable);
+        self.begin_scope();
         self.declare(&stmt.name);
         self.define(&stmt.name);
+        self.resolve_loop_body(&stmt.body);
+        self.end_scope();
+    }
+    fn visit_break_stmt(&mut self, stmt: &Break) {
+        self.resolve_loop_jump(&stmt.keyword);
+    }
+    fn visit_continue_stmt(&mut self, stmt: &Continue) {
+        self.resolve_loop_jump(&stmt.keyword);
+    }
+    fn visit_import_stmt(&mut self, stmt: &Import) {
+        for name in stmt.name.iter().chain(stmt.names.iter()) {
+            self.declare(name);
+            self.define(name);
+        }
+    }
+    fn visit_throw_stmt(&mut self, stmt: &Throw) {
+        self.resolve_e(&stmt.value);
+    }
+    fn visit_try_stmt(&mut self, stmt: &Try) {
         self.begin_scope();
-        let last = self.scopes.len() - 1;
-        self.scopes[last].insert("this".to_string(), true);
-
+        self.resolves(&stmt.body);
+        self.end_scope();
+        if let Some(name) = &stmt.name {
+            self.begin_scope();
+            self.declare(name);
+            self.define(name);
+            self.resolves(&stmt.catch_body);
+            self.end_scope();
+        }
+        if let Some(finally_body) = &stmt.finally_body {
+            self.begin_scope();
+            self.resolves(finally_body);
+            self.end_scope();
+        }
+    }
+    fn visit_class_stmt(&mut self, stmt: &Class) {
+        for expr in stmt.traits.iter() {
+            self.resolve_e(expr);
+        }
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        let mut provided: Vec<(FunctionKind, &String, &Token)> = Vec::new();
+        for (i, expr) in stmt.traits.iter().enumerate() {
+            let name = match expr {
+                Expr::Variable(v) => &v.name,
+                _ => unreachable!(),
+            };
+            if stmt.traits[..i].iter().any(|t| matches!(t, Expr::Variable(v) if v.name.lexeme == name.lexeme)) {
+                parse_error(name, "Trait already used by this class.");
+                continue;
+            }
+            let members = match self.trait_members(name) {
+                Some(members) => members,
+                None => continue,
+            };
+            for (kind, member) in members.iter() {
+                let overridden = stmt.methods.iter().any(|m| m.kind == *kind && &m.name.lexeme == member);
+                if overridden {
+                    continue;
+                }
+                if let Some((_, _, other)) = provided.iter().find(|(k, m, _)| k == kind && *m == member) {
+                    parse_error(
+                        name,
+                        &format!("Method '{}' is provided by both '{}' and '{}'.", member, other.lexeme, name.lexeme),
+                    );
+                }
+                provided.push((*kind, member, name));
+            }
+        }
+        self.resolve_methods(&stmt.methods);
+    }
+    fn visit_trait_stmt(&mut self, stmt: &Trait) {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
         for method in stmt.methods.iter() {
-            if method.name.lexeme == "init" {
-                self.resolve_function(method, FunctionType::INITIALIZER);
-            } else {
-                self.resolve_function(method, FunctionType::METHOD);
+            if method.kind == FunctionKind::METHOD && method.name.lexeme == "init" {
+                parse_error(&method.name, "A trait cannot define an initializer.");
+            }
+        }
+        let members = stmt.methods.iter().map(|m| (m.kind, m.name.lexeme.clone())).collect();
+        self.traits.last_mut().unwrap().insert(stmt.name.lexeme.clone(), members);
+        self.resolve_methods(&stmt.methods);
+    }
+    fn visit_enum_stmt(&mut self, stmt: &Enum) {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        for (i, variant) in stmt.variants.iter().enumerate() {
+            if variant.lexeme == "values" {
+                parse_error(variant, "Enum variant cannot be named 'values'.");
+            } else if stmt.variants[..i].iter().any(|v| v.lexeme == variant.lexeme) {
+                parse_error(variant, "Variant with this name already declared in this enum.");
             }
         }
-        self.end_scope();
-        self.current_class = enclosing_class;
     }
 }
@@ -1,5 +1,5 @@
 use crate::error::error;
-use crate::token::Token;
+use crate::token::{Comment, Token};
 use crate::tokentype::{Literals, TokenType};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -8,6 +8,9 @@ static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     let mut m = HashMap::new();
     m.insert("and", TokenType::AND);
     m.insert("class", TokenType::CLASS);
+    m.insert("trait", TokenType::TRAIT);
+    m.insert("with", TokenType::WITH);
+    m.insert("enum", TokenType::ENUM);
     m.insert("if", TokenType::IF);
     m.insert("else", TokenType::ELSE);
     m.insert("true", TokenType::TRUE);
@@ -18,26 +21,41 @@ static KEYWORDS: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|| {
     m.insert("or", TokenType::OR);
     m.insert("print", TokenType::PRINT);
     m.insert("return", TokenType::RETURN);
+    m.insert("yield", TokenType::YIELD);
     m.insert("super", TokenType::SUPER);
     m.insert("this", TokenType::THIS);
     m.insert("var", TokenType::VAR);
+    m.insert("const", TokenType::CONST);
     m.insert("while", TokenType::WHILE);
+    m.insert("throw", TokenType::THROW);
+    m.insert("try", TokenType::TRY);
+    m.insert("catch", TokenType::CATCH);
+    m.insert("finally", TokenType::FINALLY);
+    m.insert("import", TokenType::IMPORT);
+    m.insert("from", TokenType::FROM);
+    m.insert("as", TokenType::AS);
+    m.insert("in", TokenType::IN);
+    m.insert("break", TokenType::BREAK);
+    m.insert("continue", TokenType::CONTINUE);
+    m.insert("match", TokenType::MATCH);
     m
 });
 
 pub struct Scanner<'a> {
     source: &'a String,
     tokens: Vec<Token>,
+    comments: Vec<Comment>,
     start: usize,
     current: usize,
     line: u64,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(source: &String) -> Scanner {
+    pub fn new(source: &String) -> Scanner<'_> {
         Scanner {
             source,
             tokens: Vec::new(),
+            comments: Vec::new(),
             start: 0,
             current: 0,
             line: 1,
@@ -49,16 +67,15 @@ impl<'a> Scanner<'a> {
             self.start = self.current;
             self.scan_token();
         }
-        let eof = Token::new(
-            self.tokens.len(),
-            TokenType::EOF,
-            String::from(""),
-            None,
-            self.line,
-        );
+        let eof = Token::new(TokenType::EOF, String::from(""), None, self.line);
         self.tokens.push(eof);
         &self.tokens
     }
+
+    /// Comments seen by `scan_tokens`, in source order.
+    pub fn comments(&self) -> &Vec<Comment> {
+        &self.comments
+    }
     fn is_at_end(&self) -> bool {
         self.current >= self.source.len()
     }
@@ -69,8 +86,20 @@ impl<'a> Scanner<'a> {
             ')' => self.add_token(TokenType::RIGHT_PAREN, None),
             '{' => self.add_token(TokenType::LEFT_BRACE, None),
             '}' => self.add_token(TokenType::RIGHT_BRACE, None),
+            '[' => self.add_token(TokenType::LEFT_BRACKET, None),
+            ']' => self.add_token(TokenType::RIGHT_BRACKET, None),
             ',' => self.add_token(TokenType::COMMA, None),
-            '.' => self.add_token(TokenType::DOT, None),
+            ':' => self.add_token(TokenType::COLON, None),
+            '|' => self.add_token(TokenType::PIPE, None),
+            '.' => {
+                if self.peek() == '.' && self.peek_next() == '.' {
+                    self.advance();
+                    self.advance();
+                    self.add_token(TokenType::DOT_DOT_DOT, None);
+                } else {
+                    self.add_token(TokenType::DOT, None);
+                }
+            }
             '-' => self.add_token(TokenType::MINUS, None),
             '+' => self.add_token(TokenType::PLUS, None),
             ';' => self.add_token(TokenType::SEMICOLON, None),
@@ -85,10 +114,22 @@ impl<'a> Scanner<'a> {
             '=' => {
                 if self.is_match('=') {
                     self.add_token(TokenType::EQUAL_EQUAL, None);
+                } else if self.is_match('>') {
+                    self.add_token(TokenType::ARROW, None);
                 } else {
                     self.add_token(TokenType::EQUAL, None);
                 }
             }
+            '?' => {
+                if self.is_match('?') {
+                    self.add_token(TokenType::QUESTION_QUESTION, None);
+                } else if self.peek() == '.' && !self.is_digit(self.peek_next()) {
+                    self.advance();
+                    self.add_token(TokenType::QUESTION_DOT, None);
+                } else {
+                    self.add_token(TokenType::QUESTION, None);
+                }
+            }
             '<' => {
                 if self.is_match('=') {
                     self.add_token(TokenType::LESS_EQUAL, None);
@@ -108,6 +149,12 @@ impl<'a> Scanner<'a> {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    let text = self.get_substr(self.start, self.current);
+                    self.comments.push(Comment {
+                        text: text.trim_end().to_string(),
+                        line: self.line,
+                        token: self.tokens.len(),
+                    });
                 } else {
                     self.add_token(TokenType::SLASH, None);
                 }
@@ -116,11 +163,6 @@ impl<'a> Scanner<'a> {
             '\n' => self.line += 1,
             '"' => self.string(),
             '0'..='9' => self.number(),
-            'o' => {
-                if self.is_match('r') {
-                    self.add_token(TokenType::OR, None);
-                }
-            }
             'a'..='z' | 'A'..='Z' | '_' => self.identifier(),
             _ => error(self.line, "Unexpected character."),
         }
@@ -132,13 +174,8 @@ impl<'a> Scanner<'a> {
     }
     fn add_token(&mut self, token_type: TokenType, literal: Option<Literals>) {
         let text: String = self.get_substr(self.start, self.current);
-        self.tokens.push(Token::new(
-            self.tokens.len(),
-            token_type,
-            text,
-            literal,
-            self.line,
-        ));
+        self.tokens
+            .push(Token::new(token_type, text, literal, self.line));
     }
     fn is_match(&mut self, expected: char) -> bool {
         if self.is_at_end() {
@@ -152,16 +189,10 @@ impl<'a> Scanner<'a> {
     }
 
     fn is_digit(&self, c: char) -> bool {
-        match c {
-            '0'..='9' => true,
-            _ => false,
-        }
+        c.is_ascii_digit()
     }
     fn is_alpha(&self, c: char) -> bool {
-        match c {
-            'a'..='z' | 'A'..='Z' | '_' => true,
-            _ => false,
-        }
+        matches!(c, 'a'..='z' | 'A'..='Z' | '_')
     }
     fn is_alphanumeric(&self, c: char) -> bool {
         self.is_alpha(c) || self.is_digit(c)
@@ -172,11 +203,10 @@ impl<'a> Scanner<'a> {
         }
         let text = self.get_substr(self.start, self.current);
         let keyword = KEYWORDS.get(text.as_str()).cloned();
-        let token_type: TokenType;
-        match keyword {
-            None => token_type = TokenType::IDENTIFIER,
-            Some(t) => token_type = t,
-        }
+        let token_type = match keyword {
+            None => TokenType::IDENTIFIER,
+            Some(t) => t,
+        };
         self.add_token(token_type, None);
     }
     fn peek(&self) -> char {
@@ -213,6 +243,7 @@ impl<'a> Scanner<'a> {
         let len = end - start;
         self.source.chars().skip(start).take(len).collect()
     }
+    /// Literals without a fractional part are integers.
     fn number(&mut self) {
         while self.is_digit(self.peek()) {
             self.advance();
@@ -222,8 +253,14 @@ impl<'a> Scanner<'a> {
             while self.is_digit(self.peek()) {
                 self.advance();
             }
+            let value: f64 = self.get_substr(self.start, self.current).parse().unwrap();
+            self.add_token(TokenType::NUMBER, Some(Literals::NUMBER(value)));
+            return;
         }
-        let value: f64 = self.get_substr(self.start, self.current).parse().unwrap();
-        self.add_token(TokenType::NUMBER, Some(Literals::NUMBER(value)));
+        let value = self.get_substr(self.start, self.current).parse().unwrap_or_else(|_| {
+            error(self.line, "Integer literal is too large.");
+            0
+        });
+        self.add_token(TokenType::NUMBER, Some(Literals::INTEGER(value)));
     }
 }
@@ -1,19 +1,30 @@
-use crate::token::Token;
-// use crate::tokentype::Literals;
+// Generated by src/bin/gen_ast.rs; edit the node descriptions there.
 use crate::expr::Expr;
+use crate::token::Token;
+use crate::tokentype::FunctionKind;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub enum Stmt {
-    Expression(Expression),
-    Print(Print),
-    Var(Var),
-    Block(Block),
-    If(If),
-    While(While),
-    // For(For),
-    Function(Function),
-    Return(Return),
-    Class(Class),
+    Expression(Rc<Expression>),
+    Print(Rc<Print>),
+    Var(Rc<Var>),
+    Block(Rc<Block>),
+    If(Rc<If>),
+    While(Rc<While>),
+    For(Rc<For>),
+    ForIn(Rc<ForIn>),
+    Break(Rc<Break>),
+    Continue(Rc<Continue>),
+    Function(Rc<Function>),
+    Return(Rc<Return>),
+    Yield(Rc<Yield>),
+    Class(Rc<Class>),
+    Trait(Rc<Trait>),
+    Enum(Rc<Enum>),
+    Throw(Rc<Throw>),
+    Import(Rc<Import>),
+    Try(Rc<Try>),
 }
 
 #[derive(Debug, Clone)]
@@ -23,13 +34,15 @@ pub struct Expression {
 
 #[derive(Debug, Clone)]
 pub struct Print {
+    pub keyword: Token,
     pub expression: Box<Expr>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Var {
     pub name: Token,
-    pub initializer: Box<Expr>,
+    pub initializer: Option<Box<Expr>>,
+    pub constant: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -52,53 +65,127 @@ pub struct While {
     pub body: Box<Stmt>,
 }
 
+#[derive(Debug, Clone)]
+pub struct For {
+    pub token: Token,
+    pub initializer: Option<Box<Stmt>>,
+    pub condition: Option<Box<Expr>>,
+    pub increment: Option<Box<Expr>>,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForIn {
+    pub token: Token,
+    pub name: Token,
+    pub iterable: Box<Expr>,
+    pub body: Box<Stmt>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Break {
+    pub keyword: Token,
+}
+
+#[derive(Debug, Clone)]
+pub struct Continue {
+    pub keyword: Token,
+}
+
 #[derive(Debug, Clone)]
 pub struct Function {
     pub name: Token,
+    pub kind: FunctionKind,
     pub params: Vec<Token>,
+    pub defaults: Vec<Option<Expr>>,
+    pub rest: Option<Token>,
     pub body: Vec<Stmt>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Return {
+    pub keyword: Token,
+    pub value: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Yield {
     pub keyword: Token,
     pub value: Box<Expr>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Class {
+    pub name: Token,
+    pub traits: Vec<Expr>,
+    pub methods: Vec<Function>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trait {
     pub name: Token,
     pub methods: Vec<Function>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Enum {
+    pub name: Token,
+    pub variants: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Throw {
+    pub keyword: Token,
+    pub value: Box<Expr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub keyword: Token,
+    pub path: Token,
+    pub name: Option<Token>,
+    pub names: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Try {
+    pub keyword: Token,
+    pub body: Vec<Stmt>,
+    pub name: Option<Token>,
+    pub catch_body: Vec<Stmt>,
+    pub finally_body: Option<Vec<Stmt>>,
+}
+
 impl Expression {
     pub fn new(expression: Expr) -> Stmt {
-        Stmt::Expression(Expression {
+        Stmt::Expression(Rc::new(Expression {
             expression: Box::new(expression),
-        })
+        }))
     }
 }
 
 impl Print {
-    pub fn new(expression: Expr) -> Stmt {
-        Stmt::Print(Print {
+    pub fn new(keyword: Token, expression: Expr) -> Stmt {
+        Stmt::Print(Rc::new(Print {
+            keyword,
             expression: Box::new(expression),
-        })
+        }))
     }
 }
 
 impl Var {
-    pub fn new(name: Token, initializer: Expr) -> Stmt {
-        Stmt::Var(Var {
+    pub fn new(name: Token, initializer: Option<Expr>, constant: bool) -> Stmt {
+        Stmt::Var(Rc::new(Var {
             name,
-            initializer: Box::new(initializer),
-        })
+            initializer: initializer.map(Box::new),
+            constant,
+        }))
     }
 }
 
 impl Block {
     pub fn new(statements: Vec<Stmt>) -> Stmt {
-        Stmt::Block(Block { statements })
+        Stmt::Block(Rc::new(Block { statements }))
     }
 }
 
@@ -109,59 +196,163 @@ impl If {
         then_branch: Stmt,
         else_branch: Option<Stmt>,
     ) -> Stmt {
-        Stmt::If(If {
+        Stmt::If(Rc::new(If {
             token,
             condition: Box::new(condition),
             then_branch: Box::new(then_branch),
             else_branch: else_branch.map(Box::new),
-        })
+        }))
     }
 }
 
 impl While {
     pub fn new(token: Token, condition: Expr, body: Stmt) -> Stmt {
-        Stmt::While(While {
+        Stmt::While(Rc::new(While {
             token,
             condition: Box::new(condition),
             body: Box::new(body),
-        })
+        }))
+    }
+}
+
+impl For {
+    pub fn new(
+        token: Token,
+        initializer: Option<Stmt>,
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Stmt,
+    ) -> Stmt {
+        Stmt::For(Rc::new(For {
+            token,
+            initializer: initializer.map(Box::new),
+            condition: condition.map(Box::new),
+            increment: increment.map(Box::new),
+            body: Box::new(body),
+        }))
+    }
+}
+
+impl ForIn {
+    pub fn new(token: Token, name: Token, iterable: Expr, body: Stmt) -> Stmt {
+        Stmt::ForIn(Rc::new(ForIn {
+            token,
+            name,
+            iterable: Box::new(iterable),
+            body: Box::new(body),
+        }))
+    }
+}
+
+impl Break {
+    pub fn new(keyword: Token) -> Stmt {
+        Stmt::Break(Rc::new(Break { keyword }))
+    }
+}
+
+impl Continue {
+    pub fn new(keyword: Token) -> Stmt {
+        Stmt::Continue(Rc::new(Continue { keyword }))
     }
 }
 
 impl Function {
-    pub fn new(name: Token, params: Vec<Token>, body: Vec<Stmt>) -> Stmt {
-        Stmt::Function(Function { name, params, body })
+    pub fn new(
+        name: Token,
+        kind: FunctionKind,
+        params: Vec<Token>,
+        defaults: Vec<Option<Expr>>,
+        rest: Option<Token>,
+        body: Vec<Stmt>,
+    ) -> Stmt {
+        Stmt::Function(Rc::new(Function {
+            name,
+            kind,
+            params,
+            defaults,
+            rest,
+            body,
+        }))
     }
 }
 
 impl Return {
+    pub fn new(keyword: Token, value: Option<Expr>) -> Stmt {
+        Stmt::Return(Rc::new(Return {
+            keyword,
+            value: value.map(Box::new),
+        }))
+    }
+}
+
+impl Yield {
     pub fn new(keyword: Token, value: Expr) -> Stmt {
-        Stmt::Return(Return {
+        Stmt::Yield(Rc::new(Yield {
             keyword,
             value: Box::new(value),
-        })
+        }))
     }
 }
 
 impl Class {
+    pub fn new(name: Token, traits: Vec<Expr>, methods: Vec<Function>) -> Stmt {
+        Stmt::Class(Rc::new(Class {
+            name,
+            traits,
+            methods,
+        }))
+    }
+}
+
+impl Trait {
     pub fn new(name: Token, methods: Vec<Function>) -> Stmt {
-        Stmt::Class(Class { name, methods })
+        Stmt::Trait(Rc::new(Trait { name, methods }))
     }
 }
 
-/*
-impl For {
-    pub fn new(token: Token, initializer: Option<Stmt>, condition: Option<Expr>, increment: Option<Expr>, body: Stmt) -> Stmt {
-        Stmt::For(For {
-            token,
-            initializer: initializer.map(Box::new),
-            condition: condition.map(Box::new),
-            increment: increment.map(Box::new),
-            body: Box::new(body),
-        })
+impl Enum {
+    pub fn new(name: Token, variants: Vec<Token>) -> Stmt {
+        Stmt::Enum(Rc::new(Enum { name, variants }))
+    }
+}
+
+impl Throw {
+    pub fn new(keyword: Token, value: Expr) -> Stmt {
+        Stmt::Throw(Rc::new(Throw {
+            keyword,
+            value: Box::new(value),
+        }))
+    }
+}
+
+impl Import {
+    pub fn new(keyword: Token, path: Token, name: Option<Token>, names: Vec<Token>) -> Stmt {
+        Stmt::Import(Rc::new(Import {
+            keyword,
+            path,
+            name,
+            names,
+        }))
+    }
+}
+
+impl Try {
+    pub fn new(
+        keyword: Token,
+        body: Vec<Stmt>,
+        name: Option<Token>,
+        catch_body: Vec<Stmt>,
+        finally_body: Option<Vec<Stmt>>,
+    ) -> Stmt {
+        Stmt::Try(Rc::new(Try {
+            keyword,
+            body,
+            name,
+            catch_body,
+            finally_body,
+        }))
     }
 }
-*/
 
 impl Stmt {
     pub fn accept<T, V: Visitor<T>>(&self, visitor: &mut V) -> T {
@@ -172,9 +363,19 @@ impl Stmt {
             Stmt::Block(e) => visitor.visit_block_stmt(e),
             Stmt::If(e) => visitor.visit_if_stmt(e),
             Stmt::While(e) => visitor.visit_while_stmt(e),
+            Stmt::For(e) => visitor.visit_for_stmt(e),
+            Stmt::ForIn(e) => visitor.visit_for_in_stmt(e),
+            Stmt::Break(e) => visitor.visit_break_stmt(e),
+            Stmt::Continue(e) => visitor.visit_continue_stmt(e),
             Stmt::Function(e) => visitor.visit_function_stmt(e),
             Stmt::Return(e) => visitor.visit_return_stmt(e),
+            Stmt::Yield(e) => visitor.visit_yield_stmt(e),
             Stmt::Class(e) => visitor.visit_class_stmt(e),
+            Stmt::Trait(e) => visitor.visit_trait_stmt(e),
+            Stmt::Enum(e) => visitor.visit_enum_stmt(e),
+            Stmt::Throw(e) => visitor.visit_throw_stmt(e),
+            Stmt::Import(e) => visitor.visit_import_stmt(e),
+            Stmt::Try(e) => visitor.visit_try_stmt(e),
         }
     }
 }
@@ -186,7 +387,17 @@ pub trait Visitor<T> {
     fn visit_block_stmt(&mut self, stmt: &Block) -> T;
     fn visit_if_stmt(&mut self, stmt: &If) -> T;
     fn visit_while_stmt(&mut self, stmt: &While) -> T;
+    fn visit_for_stmt(&mut self, stmt: &For) -> T;
+    fn visit_for_in_stmt(&mut self, stmt: &ForIn) -> T;
+    fn visit_break_stmt(&mut self, stmt: &Break) -> T;
+    fn visit_continue_stmt(&mut self, stmt: &Continue) -> T;
     fn visit_function_stmt(&mut self, stmt: &Function) -> T;
     fn visit_return_stmt(&mut self, stmt: &Return) -> T;
+    fn visit_yield_stmt(&mut self, stmt: &Yield) -> T;
     fn visit_class_stmt(&mut self, stmt: &Class) -> T;
+    fn visit_trait_stmt(&mut self, stmt: &Trait) -> T;
+    fn visit_enum_stmt(&mut self, stmt: &Enum) -> T;
+    fn visit_throw_stmt(&mut self, stmt: &Throw) -> T;
+    fn visit_import_stmt(&mut self, stmt: &Import) -> T;
+    fn visit_try_stmt(&mut self, stmt: &Try) -> T;
 }
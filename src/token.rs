@@ -1,8 +1,13 @@
 use crate::tokentype::{Literals, TokenType};
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
 
 #[derive(Debug, Clone)]
 pub struct Token {
+    /// Unique across sources, so the resolver can record what a name
+    /// declares.
     pub id: usize,
     pub token_type: TokenType,
     pub lexeme: String,
@@ -12,14 +17,13 @@ pub struct Token {
 
 impl Token {
     pub fn new(
-        id: usize,
         token_type: TokenType,
         lexeme: String,
         literal: Option<Literals>,
         line: u64,
     ) -> Token {
         Token {
-            id,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
             token_type,
             lexeme,
             literal,
@@ -37,3 +41,12 @@ impl fmt::Display for Token {
         )
     }
 }
+
+/// A `//` comment, kept by the scanner as trivia rather than as a token.
+/// `token` is the index of the token that follows it.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub text: String,
+    pub line: u64,
+    pub token: usize,
+}
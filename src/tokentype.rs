@@ -1,20 +1,36 @@
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum Literals {
     STRING(String),
     NUMBER(f64),
+    INTEGER(i64),
     BOOL(bool),
     NIL(Option<()>),
 }
 
+/// How a `Function` was declared: at the top level or as a class member.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FunctionKind {
+    FUNCTION,
+    METHOD,
+    STATIC,
+    GETTER,
+    SETTER,
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TokenType {
     // Single-character tokens.
     LEFT_PAREN,
     RIGHT_PAREN,
     LEFT_BRACE,
     RIGHT_BRACE,
+    LEFT_BRACKET,
+    RIGHT_BRACKET,
     COMMA,
+    COLON,
+    PIPE,
     DOT,
     MINUS,
     PLUS,
@@ -30,6 +46,11 @@ pub enum TokenType {
     GREATER_EQUAL,
     LESS,
     LESS_EQUAL,
+    QUESTION,
+    QUESTION_QUESTION,
+    QUESTION_DOT,
+    ARROW,
+    DOT_DOT_DOT,
     // Literals.
     IDENTIFIER,
     STRING,
@@ -37,6 +58,9 @@ pub enum TokenType {
     // Keywords.
     AND,
     CLASS,
+    TRAIT,
+    WITH,
+    ENUM,
     ELSE,
     FALSE,
     FUN,
@@ -46,11 +70,24 @@ pub enum TokenType {
     OR,
     PRINT,
     RETURN,
+    YIELD,
     SUPER,
     THIS,
     TRUE,
     VAR,
+    CONST,
     WHILE,
+    THROW,
+    TRY,
+    CATCH,
+    FINALLY,
+    IMPORT,
+    FROM,
+    AS,
+    IN,
+    BREAK,
+    CONTINUE,
+    MATCH,
 
     EOF,
 }
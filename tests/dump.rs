@@ -0,0 +1,46 @@
+use lox::dump::{ast_json, ast_sexpr, tokens_json};
+use lox::parser::Parser;
+use lox::scanner::Scanner;
+
+fn sexpr(source: &str) -> String {
+    let source = source.to_string();
+    let mut scanner = Scanner::new(&source);
+    let tokens = scanner.scan_tokens();
+    ast_sexpr(&Parser::new(tokens).parse().unwrap())
+}
+
+#[test]
+fn sexpr_covers_statements_and_expressions() {
+    assert_eq!(
+        sexpr("var a = -1 + 2 * (3);\nprint a.b = f(nil, \"s\");"),
+        "(var a (binary + (unary - (literal 1)) (binary * (literal 2) (grouping (literal 3)))))\n\
+         (print (set (variable a) b (call (variable f) [(literal nil) (literal \"s\")] [])))\n"
+    );
+    assert_eq!(
+        sexpr("for (;;) {} class A { m(x) { return this; } }"),
+        "(for nil nil nil (block []))\n\
+         (class A [] [(function m method [x] [nil] nil [(return (this))])])\n"
+    );
+}
+
+#[test]
+fn json_dumps() {
+    let source = "var s = \"a\\b\";".to_string();
+    let mut scanner = Scanner::new(&source);
+    let tokens = scanner.scan_tokens();
+    assert_eq!(
+        tokens_json(tokens),
+        "[\n  {\"type\": \"VAR\", \"lexeme\": \"var\", \"literal\": null, \"line\": 1},\n  \
+         {\"type\": \"IDENTIFIER\", \"lexeme\": \"s\", \"literal\": null, \"line\": 1},\n  \
+         {\"type\": \"EQUAL\", \"lexeme\": \"=\", \"literal\": null, \"line\": 1},\n  \
+         {\"type\": \"STRING\", \"lexeme\": \"\\\"a\\\\b\\\"\", \"literal\": \"a\\\\b\", \"line\": 1},\n  \
+         {\"type\": \"SEMICOLON\", \"lexeme\": \";\", \"literal\": null, \"line\": 1},\n  \
+         {\"type\": \"EOF\", \"lexeme\": \"\", \"literal\": null, \"line\": 1}\n]\n"
+    );
+    let statements = Parser::new(tokens).parse().unwrap();
+    assert_eq!(
+        ast_json(&statements),
+        "[\n  {\n    \"node\": \"Var\",\n    \"name\": \"s\",\n    \"initializer\": {\n      \
+         \"node\": \"Literal\",\n      \"value\": \"a\\\\b\"\n    }\n  }\n]\n"
+    );
+}
@@ -0,0 +1,82 @@
+use lox::ast_printer::format;
+use lox::scanner::Scanner;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn comments(source: &String) -> Vec<String> {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens();
+    scanner.comments().iter().map(|c| c.text.clone()).collect()
+}
+
+fn run(path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg(path)
+        .output()
+        .expect("failed to run lox")
+}
+
+/// Formatting every script is idempotent, keeps its comments and does not
+/// change what it prints.
+#[test]
+fn format_round_trips() {
+    let tests_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let out_dir = std::env::temp_dir().join(format!("lox-fmt-{}", std::process::id()));
+    fs::create_dir_all(out_dir.join("modules")).unwrap();
+    // Scripts import these relative to their own directory.
+    for entry in fs::read_dir(tests_dir.join("modules")).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, out_dir.join("modules").join(path.file_name().unwrap())).unwrap();
+    }
+
+    let mut scripts: Vec<_> = fs::read_dir(&tests_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    scripts.sort();
+
+    for script in scripts {
+        let source = fs::read_to_string(&script).unwrap();
+        let formatted = match format(&source) {
+            Ok(formatted) => formatted,
+            Err(_) => continue,
+        };
+        let name = script.file_name().unwrap();
+        assert_eq!(format(&formatted), Ok(formatted.clone()), "{:?} is not idempotent", name);
+        assert_eq!(comments(&source), comments(&formatted), "{:?} lost comments", name);
+
+        let formatted_path = out_dir.join(name);
+        fs::write(&formatted_path, &formatted).unwrap();
+        let before = run(&script);
+        let after = run(&formatted_path);
+        assert_eq!(before.status.code(), after.status.code(), "{:?} changed exit code", name);
+        assert_eq!(
+            String::from_utf8_lossy(&before.stdout),
+            String::from_utf8_lossy(&after.stdout),
+            "{:?} changed output",
+            name
+        );
+    }
+    fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn format_canonical_layout() {
+    let source = String::from(
+        "var a=1;var b;  // note\n\n\nfor(var i=0;i<3;i=i+1){print i;}\nif (a) {} else {\n// empty\n}\n",
+    );
+    let expected = "var a = 1;\nvar b; // note\n\nfor (var i = 0; i < 3; i = i + 1) {\n  print i;\n}\nif (a) {} else {\n  // empty\n}\n";
+    assert_eq!(format(&source), Ok(expected.to_string()));
+}
+
+#[test]
+fn format_comments_before_closing_tokens() {
+    let source = String::from(
+        "if (a) {\nprint 1;\n} // after if\nelse {\nprint 2;\n}\nvar m = {\n\"k\": 1 // trailing\n};\n",
+    );
+    let expected = "if (a) {\n  print 1;\n} // after if\nelse {\n  print 2;\n}\nvar m = {\"k\": 1 // trailing\n};\n";
+    assert_eq!(format(&source), Ok(expected.to_string()));
+    assert_eq!(format(&expected.to_string()), Ok(expected.to_string()));
+}
@@ -0,0 +1,27 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// `src/expr.rs` and `src/stmt.rs` must be exactly what `gen_ast` produces.
+#[test]
+fn generated_ast_is_up_to_date() {
+    let out_dir = std::env::temp_dir().join(format!("lox-gen-ast-{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_gen_ast"))
+        .arg(&out_dir)
+        .status()
+        .expect("failed to run gen_ast");
+    assert!(status.success());
+
+    let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    for file in &["expr.rs", "stmt.rs"] {
+        let generated = fs::read_to_string(out_dir.join(file)).unwrap();
+        let checked_in = fs::read_to_string(src_dir.join(file)).unwrap();
+        assert!(
+            generated == checked_in,
+            "src/{} is stale; run `cargo run --bin gen_ast -- src`",
+            file
+        );
+    }
+    fs::remove_dir_all(&out_dir).unwrap();
+}
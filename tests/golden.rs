@@ -0,0 +1,139 @@
+//! Runs every `tests/*.lox` script through the `lox` binary and checks its
+//! output against expectations written as comments in the script, in the
+//! style of the Crafting Interpreters test suite:
+//!
+//! - `// expect: <text>` — the next line printed to stdout.
+//! - `// expect runtime error: <message>` — the script fails at runtime on
+//!   this line with `<message>` (exit code 70).
+//! - `// Error at '<lexeme>': <message>` — a compile error is reported on
+//!   this line (exit code 65); `// [line N] Error ...` names another line.
+//! - `// Warning at '<lexeme>': <message>` — a warning is reported on this
+//!   line; warnings do not change the exit code.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Default)]
+struct Expectations {
+    output: Vec<String>,
+    compile_errors: Vec<String>,
+    warnings: Vec<String>,
+    runtime_error: Option<(String, usize)>,
+}
+
+fn parse_expectations(source: &str) -> Expectations {
+    let mut expected = Expectations::default();
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let comment = match line.find("// ") {
+            Some(start) => &line[start + 3..],
+            None => continue,
+        };
+        if let Some(text) = comment.strip_prefix("expect: ") {
+            expected.output.push(text.to_string());
+        } else if let Some(message) = comment.strip_prefix("expect runtime error: ") {
+            expected.runtime_error = Some((message.to_string(), line_number));
+        } else if comment.starts_with("Error at ") || comment.starts_with("Error: ") {
+            expected
+                .compile_errors
+                .push(format!("[line {}] {}", line_number, comment));
+        } else if comment.starts_with("Warning at ") {
+            expected.warnings.push(format!("[line {}] {}", line_number, comment));
+        } else if comment.starts_with("[line ") && comment.contains("] Error") {
+            expected.compile_errors.push(comment.to_string());
+        }
+    }
+    expected
+}
+
+fn check(path: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let expected = parse_expectations(&source);
+    let result = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    let output: Vec<&str> = stdout.lines().collect();
+    let (warnings, errors): (Vec<&str>, Vec<&str>) =
+        stderr.lines().partition(|line| line.starts_with("[line ") && line.contains("] Warning"));
+    let code = result.status.code();
+
+    if output != expected.output {
+        return Err(format!(
+            "expected output {:?}\n  got {:?}",
+            expected.output, output
+        ));
+    }
+    if warnings != expected.warnings {
+        return Err(format!(
+            "expected warnings {:?}\n  got {:?}",
+            expected.warnings, warnings
+        ));
+    }
+    if !expected.compile_errors.is_empty() {
+        if errors != expected.compile_errors {
+            return Err(format!(
+                "expected compile errors {:?}\n  got {:?}",
+                expected.compile_errors, errors
+            ));
+        }
+        if code != Some(65) {
+            return Err(format!("expected exit code 65, got {:?}", code));
+        }
+    } else if let Some((message, line)) = &expected.runtime_error {
+        if errors.first() != Some(&message.as_str()) {
+            return Err(format!(
+                "expected runtime error {:?}\n  got {:?}",
+                message, errors
+            ));
+        }
+        let location = format!("[line {}]", line);
+        if !errors.get(1).is_some_and(|frame| frame.starts_with(&location)) {
+            return Err(format!("expected error {}\n  got {:?}", location, errors));
+        }
+        if code != Some(70) {
+            return Err(format!("expected exit code 70, got {:?}", code));
+        }
+    } else {
+        if !errors.is_empty() {
+            return Err(format!("unexpected errors {:?}", errors));
+        }
+        if code != Some(0) {
+            return Err(format!("expected exit code 0, got {:?}", code));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn golden_scripts() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let mut scripts: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    scripts.sort();
+
+    let mut failures = Vec::new();
+    for script in scripts.iter() {
+        let name = script.file_name().unwrap().to_string_lossy();
+        match check(script) {
+            Ok(()) => println!("PASS {}", name),
+            Err(reason) => {
+                println!("FAIL {}: {}", name, reason);
+                failures.push(name.to_string());
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts failed: {}",
+        failures.len(),
+        scripts.len(),
+        failures.join(", ")
+    );
+}